@@ -0,0 +1,58 @@
+// Bits of the sound registers 0xff10-0xff2f that always read as 1.
+const REGISTER_MASKS: [u8; 0x20] = [
+    0x80, 0x3f, 0x00, 0xff, 0xbf, 0xff, 0x3f, 0x00, 0xff, 0xbf, 0x7f, 0xff, 0x9f, 0xff, 0xbf, 0xff,
+    0xff, 0x00, 0x00, 0xbf, 0x00, 0x00, 0x70, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+];
+
+pub const SAMPLE_RATE: u32 = 48000;
+const CLOCK_RATE: u32 = 4194304;
+
+// Sound registers 0xff10-0xff3f, including the wave pattern RAM at 0xff30-0xff3f.
+// The channels are not synthesized yet, the output is silence at the right sample rate.
+pub struct Apu {
+    registers: [u8; 0x30],
+    sample_counter: u32,
+    // Interleaved left/right samples produced since the last clear_samples().
+    pub samples: Vec<i16>,
+}
+
+impl Apu {
+    pub fn new() -> Apu {
+        return Apu {
+            registers: [0; 0x30],
+            sample_counter: 0,
+            samples: Vec::new(),
+        };
+    }
+
+    pub fn read(&self, addr: u16) -> u8 {
+        let index = addr as usize - 0xff10;
+        if index < REGISTER_MASKS.len() {
+            return self.registers[index] | REGISTER_MASKS[index];
+        }
+        return self.registers[index];
+    }
+
+    pub fn write(&mut self, addr: u16, value: u8) {
+        self.registers[addr as usize - 0xff10] = value;
+    }
+
+    pub fn tick(&mut self, clocks: u32) {
+        self.sample_counter += clocks * SAMPLE_RATE;
+        while self.sample_counter >= CLOCK_RATE {
+            self.sample_counter -= CLOCK_RATE;
+            self.samples.push(0);
+            self.samples.push(0);
+        }
+    }
+
+    pub fn clear_samples(&mut self) {
+        self.samples.clear();
+    }
+}
+
+impl Default for Apu {
+    fn default() -> Apu {
+        return Apu::new();
+    }
+}
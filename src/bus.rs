@@ -0,0 +1,328 @@
+use crate::apu::Apu;
+use crate::cartridge::Cartridge;
+use crate::cpu::Model;
+use crate::joypad::Joypad;
+use crate::serial::Serial;
+use crate::timer::Timer;
+use crate::video::Video;
+
+// Everything the CPU sees through its address space. Interrupts are requested through
+// IF at 0xff0f and enabled through IE at 0xffff, like on the real hardware.
+pub trait Bus {
+    fn read(&self, addr: u16) -> u8;
+    fn write(&mut self, addr: u16, value: u8);
+
+    // Advance the hardware behind the bus by the given number of machine cycles.
+    fn tick(&mut self, _cycles: u32) {}
+
+    // Called on STOP, returns true when the CGB speed switch was performed instead of stopping.
+    fn stop(&mut self) -> bool {
+        return false;
+    }
+
+    // Polled while stopped, returns true when the CPU should leave STOP mode.
+    fn wake_from_stop(&mut self) -> bool {
+        return true;
+    }
+}
+
+// The Game Boy memory map with all of its hardware.
+pub struct SystemBus {
+    model: Model,
+    double_speed: bool,
+    speed_switch_armed: bool,
+    // In STOP mode the timer, serial port and LCD do not run.
+    stopped: bool,
+
+    pub cartridge: Cartridge,
+    boot_rom: Vec<u8>,
+
+    wram: [u8; 0x2000],
+    hram: [u8; 0x80],
+
+    reg_if: u8,
+    reg_ie: u8,
+
+    pub video: Video,
+    pub apu: Apu,
+    pub timer: Timer,
+    pub serial: Serial,
+    pub joypad: Joypad,
+}
+
+impl SystemBus {
+    pub fn new(cartridge: Cartridge, boot_rom: Vec<u8>, model: Model) -> SystemBus {
+        return SystemBus {
+            model: model,
+            double_speed: false,
+            speed_switch_armed: false,
+            stopped: false,
+
+            cartridge: cartridge,
+            boot_rom: boot_rom,
+
+            wram: [0; 0x2000],
+            hram: [0; 0x80],
+
+            reg_if: 0,
+            reg_ie: 0,
+
+            video: Video::new(),
+            apu: Apu::new(),
+            timer: Timer::new(),
+            serial: Serial::new(),
+            joypad: Joypad::new(),
+        };
+    }
+
+    pub fn save(&mut self) {
+        self.cartridge.save();
+    }
+}
+
+impl Bus for SystemBus {
+    fn read(&self, addr: u16) -> u8 {
+        // The boot ROM overlays the cartridge until it is disabled by a write to 0xff50,
+        // the CGB boot ROM leaves 0x0100-0x01ff mapped to the cartridge header.
+        if !(0x0100..0x0200).contains(&addr) && (addr as usize) < self.boot_rom.len() {
+            return self.boot_rom[addr as usize];
+        }
+        if addr < 0x8000 {
+            return self.cartridge.read(addr);
+        } else if addr < 0xa000 {
+            return self.video.vram[addr as usize - 0x8000];
+        } else if addr < 0xc000 {
+            return self.cartridge.read(addr);
+        } else if addr < 0xe000 {
+            return self.wram[addr as usize - 0xc000];
+        } else if addr < 0xfe00 {
+            // Echo RAM mirrors 0xc000-0xddff.
+            return self.wram[addr as usize - 0xe000];
+        } else if addr < 0xfea0 {
+            return self.video.oam[addr as usize - 0xfe00];
+        } else if addr < 0xff00 {
+            // The unusable area reads as zero on DMG, CGB returns the high address nibble twice.
+            if self.model == Model::CGB {
+                return (addr as u8 & 0xf0) | ((addr as u8 & 0xf0) >> 4);
+            }
+            return 0x00;
+        } else if addr == 0xff00 {
+            return self.joypad.read();
+        } else if addr == 0xff01 {
+            return self.serial.data;
+        } else if addr == 0xff02 {
+            return self.serial.control();
+        } else if addr == 0xff04 {
+            return self.timer.div();
+        } else if addr == 0xff05 {
+            return self.timer.tima;
+        } else if addr == 0xff06 {
+            return self.timer.tma;
+        } else if addr == 0xff07 {
+            return self.timer.tac();
+        } else if addr == 0xff0f {
+            return self.reg_if | 0xe0;
+        } else if (0xff10..0xff40).contains(&addr) {
+            return self.apu.read(addr);
+        } else if addr == 0xff40 {
+            return self.video.lcdc;
+        } else if addr == 0xff41 {
+            return self.video.stat | 0x80;
+        } else if addr == 0xff42 {
+            return self.video.scy;
+        } else if addr == 0xff43 {
+            return self.video.scx;
+        } else if addr == 0xff44 {
+            return self.video.ly;
+        } else if addr == 0xff45 {
+            return self.video.lyc;
+        } else if addr == 0xff47 {
+            return self.video.bgp;
+        } else if addr == 0xff48 {
+            return self.video.obp0;
+        } else if addr == 0xff49 {
+            return self.video.obp1;
+        } else if addr == 0xff4a {
+            return self.video.wy;
+        } else if addr == 0xff4b {
+            return self.video.wx;
+        } else if addr == 0xff4d && self.model == Model::CGB {
+            let mut result = 0x7e;
+            if self.double_speed {
+                result |= 0x80;
+            }
+            if self.speed_switch_armed {
+                result |= 0x01;
+            }
+            return result;
+        } else if (0xff80..0xffff).contains(&addr) {
+            return self.hram[addr as usize - 0xff80];
+        } else if addr == 0xffff {
+            return self.reg_ie;
+        }
+        // Unmapped IO registers read as all ones.
+        return 0xff;
+    }
+
+    fn write(&mut self, addr: u16, value: u8) {
+        if addr < 0x8000 || (0xa000..0xc000).contains(&addr) {
+            self.cartridge.write(addr, value);
+        } else if (0xc000..0xe000).contains(&addr) {
+            self.wram[addr as usize - 0xc000] = value;
+        } else if (0xe000..0xfe00).contains(&addr) {
+            self.wram[addr as usize - 0xe000] = value;
+        } else if (0xfe00..0xfea0).contains(&addr) {
+            self.video.oam[addr as usize - 0xfe00] = value;
+        } else if (0x8000..0xa000).contains(&addr) {
+            self.video.vram[addr as usize - 0x8000] = value;
+        } else if (0xff80..0xffff).contains(&addr) {
+            self.hram[addr as usize - 0xff80] = value;
+        } else if addr == 0xff00 {
+            self.joypad.write(value);
+        } else if addr == 0xff01 {
+            self.serial.data = value;
+        } else if addr == 0xff02 {
+            self.serial.write_control(value);
+        } else if addr == 0xff04 {
+            self.timer.write_div();
+        } else if addr == 0xff05 {
+            self.timer.write_tima(value);
+        } else if addr == 0xff06 {
+            self.timer.tma = value;
+        } else if addr == 0xff07 {
+            self.timer.write_tac(value);
+        } else if addr == 0xff0f {
+            self.reg_if = value & 0x1f;
+        } else if (0xff10..0xff40).contains(&addr) {
+            self.apu.write(addr, value);
+        } else if addr == 0xff40 {
+            self.video.lcdc = value;
+        } else if addr == 0xff41 {
+            self.video.write_stat(value);
+        } else if addr == 0xff42 {
+            self.video.scy = value;
+        } else if addr == 0xff43 {
+            self.video.scx = value;
+        } else if addr == 0xff45 {
+            self.video.lyc = value;
+        } else if addr == 0xff46 {
+            for n in 0..40 * 4 {
+                self.video.oam[n] = self.read((value as u16) << 8 | n as u16);
+            }
+        } else if addr == 0xff47 {
+            self.video.bgp = value;
+        } else if addr == 0xff48 {
+            self.video.obp0 = value;
+        } else if addr == 0xff49 {
+            self.video.obp1 = value;
+        } else if addr == 0xff4a {
+            self.video.wy = value;
+        } else if addr == 0xff4b {
+            self.video.wx = value;
+        } else if addr == 0xff4d {
+            if self.model == Model::CGB {
+                self.speed_switch_armed = (value & 0x01) == 0x01;
+            }
+        } else if addr == 0xff50 {
+            if value != 0 {
+                self.boot_rom.clear();
+            }
+        } else if addr == 0xffff {
+            self.reg_ie = value;
+        }
+        // Writes to read only and unmapped addresses are ignored.
+    }
+
+    // Advance all hardware by the given number of machine cycles (4 clocks each, 2 in double speed mode).
+    fn tick(&mut self, cycles: u32) {
+        let clocks = if self.double_speed {
+            cycles * 2
+        } else {
+            cycles * 4
+        };
+        if self.stopped {
+            for _ in 0..clocks {
+                self.video.step_off();
+            }
+        } else {
+            for _ in 0..cycles * 4 {
+                self.timer.step();
+            }
+            self.serial.tick(cycles * 4);
+            for _ in 0..clocks {
+                self.video.step();
+            }
+        }
+        self.apu.tick(clocks);
+        self.cartridge.tick(clocks);
+        if self.video.vblank_interrupt {
+            self.video.vblank_interrupt = false;
+            self.reg_if |= 0x01;
+        }
+        if self.video.stat_interrupt {
+            self.video.stat_interrupt = false;
+            self.reg_if |= 0x02;
+        }
+        if self.timer.interrupt {
+            self.timer.interrupt = false;
+            self.reg_if |= 0x04;
+        }
+        if self.serial.interrupt {
+            self.serial.interrupt = false;
+            self.reg_if |= 0x08;
+        }
+        if self.joypad.interrupt {
+            self.joypad.interrupt = false;
+            self.reg_if |= 0x10;
+        }
+    }
+
+    fn stop(&mut self) -> bool {
+        self.timer.write_div();
+        if self.speed_switch_armed {
+            self.speed_switch_armed = false;
+            self.double_speed = !self.double_speed;
+            return true;
+        }
+        self.stopped = true;
+        return false;
+    }
+
+    // STOP mode ends when one of the selected P1 input lines goes low.
+    fn wake_from_stop(&mut self) -> bool {
+        if (self.joypad.read() & 0x0f) != 0x0f {
+            self.stopped = false;
+            return true;
+        }
+        return false;
+    }
+}
+
+// 64 KiB of plain RAM without any hardware behind it, to run the CPU in isolation.
+pub struct FlatBus {
+    pub memory: Vec<u8>,
+}
+
+impl FlatBus {
+    pub fn new() -> FlatBus {
+        return FlatBus {
+            memory: vec![0; 0x10000],
+        };
+    }
+}
+
+impl Default for FlatBus {
+    fn default() -> FlatBus {
+        return FlatBus::new();
+    }
+}
+
+impl Bus for FlatBus {
+    fn read(&self, addr: u16) -> u8 {
+        return self.memory[addr as usize];
+    }
+
+    fn write(&mut self, addr: u16, value: u8) {
+        self.memory[addr as usize] = value;
+    }
+}
@@ -0,0 +1,334 @@
+use crate::mbc;
+use std::fs::File;
+use std::io::Read;
+use std::path::PathBuf;
+
+// Battery backed RAM is written back this often (in clocks) when it has been modified.
+const SAVE_INTERVAL: u32 = 4194304 * 5;
+
+// Cartridge types with a battery to keep RAM or the clock running.
+const BATTERY_TYPES: [u8; 14] = [
+    0x03, 0x06, 0x09, 0x0d, 0x0f, 0x10, 0x13, 0x1b, 0x1e, 0x20, 0x22, 0xfc, 0xfe, 0xff,
+];
+
+const NINTENDO_LOGO: [u8; 48] = [
+    0xce, 0xed, 0x66, 0x66, 0xcc, 0x0d, 0x00, 0x0b, 0x03, 0x73, 0x00, 0x83, 0x00, 0x0c, 0x00, 0x0d,
+    0x00, 0x08, 0x11, 0x1f, 0x88, 0x89, 0x00, 0x0e, 0xdc, 0xcc, 0x6e, 0xe6, 0xdd, 0xdd, 0xd9, 0x99,
+    0xbb, 0xbb, 0x67, 0x63, 0x6e, 0x0e, 0xec, 0xcc, 0xdd, 0xdc, 0x99, 0x9f, 0xbb, 0xb9, 0x33, 0x3e,
+];
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum MbcType {
+    None,
+    MBC1,
+    MBC2,
+    MBC3,
+    MBC5,
+    MBC6,
+    MBC7,
+    MMM01,
+    HuC1,
+    HuC3,
+    PocketCamera,
+    Unknown,
+}
+
+pub struct Cartridge {
+    rom: Vec<u8>,
+    ram: Vec<u8>,
+
+    pub title: String,
+    pub manufacturer_code: String,
+    pub cgb_flag: u8,
+    pub sgb_flag: u8,
+    pub cartridge_type: u8,
+    pub rom_size: usize,
+    pub ram_size: usize,
+    pub licensee: String,
+    pub version: u8,
+    pub header_checksum: u8,
+    pub global_checksum: u16,
+
+    mbc: Box<dyn mbc::Mbc>,
+
+    save_filename: Option<PathBuf>,
+    save_dirty: bool,
+    save_timer: u32,
+}
+
+impl Cartridge {
+    pub fn load(filename: &str, rtc_mode: mbc::RtcMode) -> Result<Cartridge, String> {
+        let mut file = File::open(filename).map_err(|e| format!("{}: {}", filename, e))?;
+        let mut data = Vec::new();
+        file.read_to_end(&mut data)
+            .map_err(|e| format!("{}: {}", filename, e))?;
+        return Cartridge::new(data, rtc_mode).map_err(|e| format!("{}: {}", filename, e));
+    }
+
+    pub fn new(rom: Vec<u8>, rtc_mode: mbc::RtcMode) -> Result<Cartridge, String> {
+        if rom.len() < 0x150 {
+            return Err("too small to contain a cartridge header".to_string());
+        }
+        let cgb_flag = rom[0x143];
+        // Newer carts use the end of the title area for the manufacturer code and CGB flag.
+        let mut title_end = 0x144;
+        let mut manufacturer_code = String::new();
+        if (cgb_flag & 0x80) == 0x80 {
+            title_end = 0x143;
+            let code = &rom[0x13f..0x143];
+            if code
+                .iter()
+                .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+            {
+                title_end = 0x13f;
+                manufacturer_code = String::from_utf8_lossy(code).to_string();
+            }
+        }
+        let title = rom[0x134..title_end]
+            .iter()
+            .take_while(|c| **c != 0)
+            .map(|c| *c as char)
+            .collect();
+        let licensee = if rom[0x14b] == 0x33 {
+            String::from_utf8_lossy(&rom[0x144..0x146]).to_string()
+        } else {
+            format!("{:02x}", rom[0x14b])
+        };
+        let rom_size = match rom[0x148] {
+            0x00..=0x08 => 0x8000 << rom[0x148],
+            0x52 => 72 * 0x4000,
+            0x53 => 80 * 0x4000,
+            0x54 => 96 * 0x4000,
+            _ => rom.len(),
+        };
+        let ram_size = match rom[0x149] {
+            0x01 => 0x800,
+            0x02 => 0x2000,
+            0x03 => 0x8000,
+            0x04 => 0x20000,
+            0x05 => 0x10000,
+            _ => 0,
+        };
+
+        let mut cartridge = Cartridge {
+            title: title,
+            manufacturer_code: manufacturer_code,
+            cgb_flag: cgb_flag,
+            sgb_flag: rom[0x146],
+            cartridge_type: rom[0x147],
+            rom_size: rom_size,
+            ram_size: ram_size,
+            licensee: licensee,
+            version: rom[0x14c],
+            header_checksum: rom[0x14d],
+            global_checksum: (rom[0x14e] as u16) << 8 | rom[0x14f] as u16,
+
+            rom: rom,
+            ram: vec![0; ram_size],
+
+            mbc: Box::new(mbc::RomOnly::new()),
+
+            save_filename: None,
+            save_dirty: false,
+            save_timer: 0,
+        };
+        cartridge.mbc = match cartridge.mbc_type() {
+            MbcType::None => Box::new(mbc::RomOnly::new()),
+            MbcType::MBC1 => Box::new(mbc::Mbc1::new(cartridge.is_mbc1_multicart())),
+            MbcType::MBC2 => {
+                // The header lists no RAM for MBC2, the controller has its own.
+                cartridge.ram = vec![0; mbc::Mbc2::RAM_SIZE];
+                Box::new(mbc::Mbc2::new())
+            }
+            MbcType::MBC3 => Box::new(mbc::Mbc3::new(cartridge.has_rtc(), rtc_mode)),
+            MbcType::MBC5 => Box::new(mbc::Mbc5::new(cartridge.has_rumble())),
+            MbcType::MBC6 => Box::new(mbc::Mbc6::new()),
+            MbcType::MBC7 => {
+                // The EEPROM is not listed in the header either.
+                cartridge.ram = vec![0xff; mbc::Mbc7::RAM_SIZE];
+                Box::new(mbc::Mbc7::new())
+            }
+            MbcType::MMM01 => Box::new(mbc::Mmm01::new()),
+            MbcType::HuC1 => Box::new(mbc::HuC1::new()),
+            MbcType::HuC3 => Box::new(mbc::HuC3::new(rtc_mode)),
+            MbcType::PocketCamera => Box::new(mbc::Camera::new()),
+            // Unsupported mappers get the MBC1 ROM banking most of them share.
+            MbcType::Unknown => Box::new(mbc::Mbc1::new(false)),
+        };
+        return Ok(cartridge);
+    }
+
+    // Handles the 0x0000-0x7fff ROM and 0xa000-0xbfff external RAM areas.
+    pub fn read(&self, addr: u16) -> u8 {
+        if addr < 0x8000 {
+            return self.mbc.read_rom(&self.rom, addr);
+        }
+        return self.mbc.read_ram(&self.ram, addr);
+    }
+
+    pub fn write(&mut self, addr: u16, value: u8) {
+        if addr < 0x8000 {
+            self.mbc.write_rom(addr, value);
+            // MBC6 flash is written through the ROM area.
+            if addr >= 0x4000 && self.mbc_type() == MbcType::MBC6 {
+                self.save_dirty = true;
+            }
+        } else {
+            self.mbc.write_ram(&mut self.ram, addr, value);
+            self.save_dirty = true;
+        }
+    }
+
+    pub fn tick(&mut self, clocks: u32) {
+        self.mbc.tick(clocks);
+        if self.save_dirty {
+            self.save_timer += clocks;
+            if self.save_timer >= SAVE_INTERVAL {
+                self.save();
+            }
+        }
+    }
+
+    // Use the given file to persist battery backed RAM, loading its contents when it exists.
+    // The file holds the raw RAM contents, followed by the clock state for carts with an RTC
+    // or the flash contents for MBC6.
+    pub fn set_save_file(&mut self, filename: PathBuf) -> Result<(), String> {
+        if !self.has_battery() {
+            return Ok(());
+        }
+        if filename.exists() {
+            let mut data = Vec::new();
+            File::open(&filename)
+                .and_then(|mut file| file.read_to_end(&mut data))
+                .map_err(|e| format!("{}: {}", filename.display(), e))?;
+            let length = self.ram.len().min(data.len());
+            self.ram[..length].copy_from_slice(&data[..length]);
+            if data.len() > self.ram.len() {
+                self.mbc.load_rtc(&data[self.ram.len()..]);
+            }
+        }
+        self.save_filename = Some(filename);
+        return Ok(());
+    }
+
+    pub fn save(&mut self) {
+        self.save_dirty = false;
+        self.save_timer = 0;
+        let filename = match &self.save_filename {
+            Some(filename) => filename,
+            None => return,
+        };
+        let mut data = self.ram.clone();
+        data.extend(self.mbc.save_rtc());
+        if let Err(e) = std::fs::write(filename, data) {
+            eprintln!("Failed to write {}: {}", filename.display(), e);
+        }
+    }
+
+    pub fn rumble(&self) -> bool {
+        return self.mbc.rumble();
+    }
+
+    pub fn set_tilt(&mut self, x: f32, y: f32) {
+        self.mbc.set_tilt(x, y);
+    }
+
+    pub fn set_camera_image(&mut self, image: Vec<u8>) {
+        self.mbc.set_camera_image(image);
+    }
+
+    pub fn mbc_type(&self) -> MbcType {
+        return match self.cartridge_type {
+            0x00 | 0x08 | 0x09 => MbcType::None,
+            0x01..=0x03 => MbcType::MBC1,
+            0x05 | 0x06 => MbcType::MBC2,
+            0x0b..=0x0d => MbcType::MMM01,
+            0x0f..=0x13 => MbcType::MBC3,
+            0x19..=0x1e => MbcType::MBC5,
+            0x20 => MbcType::MBC6,
+            0x22 => MbcType::MBC7,
+            0xfc => MbcType::PocketCamera,
+            0xfe => MbcType::HuC3,
+            0xff => MbcType::HuC1,
+            _ => MbcType::Unknown,
+        };
+    }
+
+    // MBC1M multicarts are 1 MiB carts with a second game header, and so a second logo, in bank 0x10.
+    fn is_mbc1_multicart(&self) -> bool {
+        if self.rom.len() != 0x100000 {
+            return false;
+        }
+        return self.rom[0x40104..0x40134] == NINTENDO_LOGO[..];
+    }
+
+    pub fn cartridge_type_name(&self) -> &'static str {
+        return match self.cartridge_type {
+            0x00 => "ROM ONLY",
+            0x01 => "MBC1",
+            0x02 => "MBC1+RAM",
+            0x03 => "MBC1+RAM+BATTERY",
+            0x05 => "MBC2",
+            0x06 => "MBC2+BATTERY",
+            0x08 => "ROM+RAM",
+            0x09 => "ROM+RAM+BATTERY",
+            0x0b => "MMM01",
+            0x0c => "MMM01+RAM",
+            0x0d => "MMM01+RAM+BATTERY",
+            0x0f => "MBC3+TIMER+BATTERY",
+            0x10 => "MBC3+TIMER+RAM+BATTERY",
+            0x11 => "MBC3",
+            0x12 => "MBC3+RAM",
+            0x13 => "MBC3+RAM+BATTERY",
+            0x19 => "MBC5",
+            0x1a => "MBC5+RAM",
+            0x1b => "MBC5+RAM+BATTERY",
+            0x1c => "MBC5+RUMBLE",
+            0x1d => "MBC5+RUMBLE+RAM",
+            0x1e => "MBC5+RUMBLE+RAM+BATTERY",
+            0x20 => "MBC6",
+            0x22 => "MBC7+SENSOR+RUMBLE+RAM+BATTERY",
+            0xfc => "POCKET CAMERA",
+            0xfd => "BANDAI TAMA5",
+            0xfe => "HuC3",
+            0xff => "HuC1+RAM+BATTERY",
+            _ => "UNKNOWN",
+        };
+    }
+
+    pub fn has_battery(&self) -> bool {
+        return BATTERY_TYPES.contains(&self.cartridge_type);
+    }
+
+    pub fn has_rtc(&self) -> bool {
+        return self.cartridge_type == 0x0f
+            || self.cartridge_type == 0x10
+            || self.cartridge_type == 0xfe;
+    }
+
+    pub fn has_rumble(&self) -> bool {
+        return matches!(self.cartridge_type, 0x1c..=0x1e | 0x22);
+    }
+
+    pub fn logo_valid(&self) -> bool {
+        return self.rom[0x104..0x134] == NINTENDO_LOGO[..];
+    }
+
+    pub fn header_checksum_valid(&self) -> bool {
+        let mut checksum: u8 = 0;
+        for n in 0x134..0x14d {
+            checksum = checksum.wrapping_sub(self.rom[n]).wrapping_sub(1);
+        }
+        return checksum == self.header_checksum;
+    }
+
+    pub fn global_checksum_valid(&self) -> bool {
+        let mut checksum: u16 = 0;
+        for (n, value) in self.rom.iter().enumerate() {
+            if n != 0x14e && n != 0x14f {
+                checksum = checksum.wrapping_add(*value as u16);
+            }
+        }
+        return checksum == self.global_checksum;
+    }
+}
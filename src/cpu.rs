@@ -1,10 +1,32 @@
-use std::fs::File;
-use std::io::Read;
+use crate::bus::Bus;
 
-#[path = "video.rs"]
-mod video;
+// Machine cycles per base opcode, for conditional instructions the cost when the branch is not taken.
+const OPCODE_CYCLES: [u8; 256] = [
+    1, 3, 2, 2, 1, 1, 2, 1, 5, 2, 2, 2, 1, 1, 2, 1, // 0x00
+    1, 3, 2, 2, 1, 1, 2, 1, 3, 2, 2, 2, 1, 1, 2, 1, // 0x10
+    2, 3, 2, 2, 1, 1, 2, 1, 2, 2, 2, 2, 1, 1, 2, 1, // 0x20
+    2, 3, 2, 2, 3, 3, 3, 1, 2, 2, 2, 2, 1, 1, 2, 1, // 0x30
+    1, 1, 1, 1, 1, 1, 2, 1, 1, 1, 1, 1, 1, 1, 2, 1, // 0x40
+    1, 1, 1, 1, 1, 1, 2, 1, 1, 1, 1, 1, 1, 1, 2, 1, // 0x50
+    1, 1, 1, 1, 1, 1, 2, 1, 1, 1, 1, 1, 1, 1, 2, 1, // 0x60
+    2, 2, 2, 2, 2, 2, 1, 2, 1, 1, 1, 1, 1, 1, 2, 1, // 0x70
+    1, 1, 1, 1, 1, 1, 2, 1, 1, 1, 1, 1, 1, 1, 2, 1, // 0x80
+    1, 1, 1, 1, 1, 1, 2, 1, 1, 1, 1, 1, 1, 1, 2, 1, // 0x90
+    1, 1, 1, 1, 1, 1, 2, 1, 1, 1, 1, 1, 1, 1, 2, 1, // 0xa0
+    1, 1, 1, 1, 1, 1, 2, 1, 1, 1, 1, 1, 1, 1, 2, 1, // 0xb0
+    2, 3, 3, 4, 3, 4, 2, 4, 2, 4, 3, 1, 3, 6, 2, 4, // 0xc0
+    2, 3, 3, 0, 3, 4, 2, 4, 2, 4, 3, 0, 3, 0, 2, 4, // 0xd0
+    3, 3, 2, 0, 0, 4, 2, 4, 4, 1, 4, 0, 0, 0, 2, 4, // 0xe0
+    3, 3, 2, 1, 0, 4, 2, 4, 3, 2, 4, 1, 0, 0, 2, 4, // 0xf0
+];
 
-pub struct CPU {
+#[derive(Clone, Copy, PartialEq)]
+pub enum Model {
+    DMG,
+    CGB,
+}
+
+pub struct CPU<B: Bus> {
     a: u8,
     b: u8,
     c: u8,
@@ -17,228 +39,307 @@ pub struct CPU {
     sp: u16,
 
     zero: bool,
+    subtract: bool,
+    half_carry: bool,
     carry: bool,
 
     ime: bool,
+    ime_delay: u8,
+    halted: bool,
+    halt_bug: bool,
+    stopped: bool,
+    // Set by an illegal opcode, the CPU stops executing until it is reset.
+    locked: bool,
 
-    rom: Vec<u8>,
-
-    wram: [u8; 0x2000],
-    hram: [u8; 0x80],
-
-    reg_if: u8,
-    reg_ie: u8,
-
-    rombank_offset: usize,
-
-    video: video::Video,
-    start_time: std::time::Instant,
+    pub bus: B,
 }
 
-impl CPU {
-    pub fn new(filename: &str) -> CPU {
-        let mut file = File::open(filename).unwrap();
-
-        let mut data = Vec::new();
-        file.read_to_end(&mut data).unwrap();
-
+impl<B: Bus> CPU<B> {
+    // Register values as left behind by the boot ROM.
+    pub fn new(bus: B, model: Model) -> CPU<B> {
         return CPU {
-            a: 0,
-            b: 0,
-            c: 0,
-            d: 0,
-            e: 0,
-            h: 0,
-            l: 0,
+            a: if model == Model::CGB { 0x11 } else { 0x01 },
+            b: 0x00,
+            c: if model == Model::CGB { 0x00 } else { 0x13 },
+            d: if model == Model::CGB { 0xff } else { 0x00 },
+            e: if model == Model::CGB { 0x56 } else { 0xd8 },
+            h: if model == Model::CGB { 0x00 } else { 0x01 },
+            l: if model == Model::CGB { 0x0d } else { 0x4d },
 
-            pc: 0x0150,
-            sp: 0xffc0,
+            pc: 0x0100,
+            sp: 0xfffe,
 
             zero: true,
-            carry: true,
-
-            ime: true,
-
-            rom: data,
+            subtract: false,
+            half_carry: model == Model::DMG,
+            carry: model == Model::DMG,
 
-            wram: [0; 0x2000],
-            hram: [0; 0x80],
+            ime: false,
+            ime_delay: 0,
+            halted: false,
+            halt_bug: false,
+            stopped: false,
+            locked: false,
 
-            reg_if: 0,
-            reg_ie: 0,
-
-            rombank_offset: 0x4000,
-
-            video: video::Video::new(),
-            start_time: std::time::Instant::now(),
+            bus: bus,
         };
     }
 
-    pub fn step(&mut self) {
+    // Start from the power on state instead, to run a boot ROM mapped at 0x0000.
+    pub fn reset_for_boot_rom(&mut self) {
+        self.pc = 0x0000;
+        self.sp = 0x0000;
+        self.set_af(0x0000);
+        self.set_bc(0x0000);
+        self.set_de(0x0000);
+        self.set_hl(0x0000);
+    }
+
+    // Executes a single instruction and returns the number of machine cycles it took.
+    pub fn step(&mut self) -> u32 {
+        if self.locked {
+            // The rest of the hardware keeps running, but interrupts are no longer serviced.
+            self.bus.tick(1);
+            return 1;
+        }
+        if self.halted || self.stopped {
+            return self.step_halted();
+        }
+
         let pc = self.pc_inc();
-        match self.read_mem(pc) {
+        if self.halt_bug {
+            // The HALT bug makes the CPU fail to increment PC after fetching the next opcode.
+            self.halt_bug = false;
+            self.pc = pc;
+        }
+        let opcode = self.read_mem(pc);
+        let mut cycles = OPCODE_CYCLES[opcode as usize] as u32;
+        match opcode {
             0x00 => {}
             0x01 => {
                 self.set_bc(self.read_mem16(self.pc));
-                self.pc += 2;
-            }
-            0x04 => {
-                self.b = self.b.wrapping_add(1);
-                self.zero = self.b == 0;
-            }
-            0x05 => {
-                self.b = self.b.wrapping_sub(1);
-                self.zero = self.b == 0;
+                self.pc = self.pc.wrapping_add(2);
             }
+            0x02 => self.write_mem(self.bc(), self.a),
+            0x03 => self.set_bc(self.bc().wrapping_add(1)),
+            0x04 => self.b = self.instr_inc(self.b),
+            0x05 => self.b = self.instr_dec(self.b),
             0x06 => {
                 self.b = self.read_mem(self.pc);
-                self.pc += 1;
+                self.pc = self.pc.wrapping_add(1);
             }
             0x07 => {
-                self.carry = (self.a & 0x80) == 0x80;
-                self.a = (self.a >> 7) | ((self.a & 0x7f) << 1);
-            }
-            0x09 => self.set_hl(self.hl().wrapping_add(self.bc())), //carry flag...
-            0x0b => self.set_bc(self.bc().wrapping_sub(1)),
-            0x0c => {
-                self.c = self.c.wrapping_add(1);
-                self.zero = self.c == 0;
+                self.a = self.instr_rlc(self.a);
+                self.zero = false;
             }
-            0x0d => {
-                self.d = self.d.wrapping_sub(1);
-                self.zero = self.d == 0;
+            0x08 => {
+                let addr = self.read_mem16(self.pc);
+                self.pc = self.pc.wrapping_add(2);
+                self.write_mem16(addr, self.sp);
             }
+            0x09 => self.instr_add16(self.bc()),
+            0x0a => self.a = self.read_mem(self.bc()),
+            0x0b => self.set_bc(self.bc().wrapping_sub(1)),
+            0x0c => self.c = self.instr_inc(self.c),
+            0x0d => self.c = self.instr_dec(self.c),
             0x0e => {
                 self.c = self.read_mem(self.pc);
-                self.pc += 1;
+                self.pc = self.pc.wrapping_add(1);
+            }
+            0x0f => {
+                self.a = self.instr_rrc(self.a);
+                self.zero = false;
+            }
+            0x10 => {
+                self.pc = self.pc.wrapping_add(1);
+                if !self.bus.stop() {
+                    self.stopped = true;
+                }
             }
             0x11 => {
                 self.set_de(self.read_mem16(self.pc));
-                self.pc += 2;
-            }
-            0x13 => self.set_bc(self.bc().wrapping_add(1)),
-            0x15 => {
-                self.d = self.d.wrapping_sub(1);
-                self.zero = self.d == 0;
+                self.pc = self.pc.wrapping_add(2);
             }
+            0x12 => self.write_mem(self.de(), self.a),
+            0x13 => self.set_de(self.de().wrapping_add(1)),
+            0x14 => self.d = self.instr_inc(self.d),
+            0x15 => self.d = self.instr_dec(self.d),
             0x16 => {
                 self.d = self.read_mem(self.pc);
-                self.pc += 1;
+                self.pc = self.pc.wrapping_add(1);
+            }
+            0x17 => {
+                self.a = self.instr_rl(self.a);
+                self.zero = false;
             }
             0x18 => self.instr_jr(),
-            0x19 => self.set_hl(self.hl().wrapping_add(self.de())), //carry flag...
+            0x19 => self.instr_add16(self.de()),
             0x1a => self.a = self.read_mem(self.de()),
-            0x1b => self.set_bc(self.bc().wrapping_sub(1)),
-            0x1d => {
-                self.e = self.e.wrapping_sub(1);
-                self.zero = self.e == 0;
-            }
+            0x1b => self.set_de(self.de().wrapping_sub(1)),
+            0x1c => self.e = self.instr_inc(self.e),
+            0x1d => self.e = self.instr_dec(self.e),
             0x1e => {
                 self.e = self.read_mem(self.pc);
-                self.pc += 1;
+                self.pc = self.pc.wrapping_add(1);
+            }
+            0x1f => {
+                self.a = self.instr_rr(self.a);
+                self.zero = false;
             }
             0x20 => {
                 if !self.zero {
                     self.instr_jr();
+                    cycles += 1;
                 } else {
-                    self.pc += 1;
+                    self.pc = self.pc.wrapping_add(1);
                 }
             }
             0x21 => {
                 self.set_hl(self.read_mem16(self.pc));
-                self.pc += 2;
+                self.pc = self.pc.wrapping_add(2);
             }
             0x22 => {
                 self.write_mem(self.hl(), self.a);
                 self.set_hl(self.hl().wrapping_add(1))
             }
             0x23 => self.set_hl(self.hl().wrapping_add(1)),
+            0x24 => self.h = self.instr_inc(self.h),
+            0x25 => self.h = self.instr_dec(self.h),
             0x26 => {
                 self.h = self.read_mem(self.pc);
-                self.pc += 1;
+                self.pc = self.pc.wrapping_add(1);
             }
+            0x27 => self.instr_daa(),
             0x28 => {
                 if self.zero {
                     self.instr_jr();
+                    cycles += 1;
                 } else {
                     self.pc_inc();
                 }
             }
-            0x29 => self.set_hl(self.hl().wrapping_add(self.hl())), //carry flag...
+            0x29 => self.instr_add16(self.hl()),
             0x2a => {
                 self.a = self.read_mem(self.hl());
                 self.set_hl(self.hl().wrapping_add(1))
             }
             0x2b => self.set_hl(self.hl().wrapping_sub(1)),
-            0x2f => self.a = !self.a,
+            0x2c => self.l = self.instr_inc(self.l),
+            0x2d => self.l = self.instr_dec(self.l),
+            0x2e => {
+                self.l = self.read_mem(self.pc);
+                self.pc = self.pc.wrapping_add(1);
+            }
+            0x2f => {
+                self.a = !self.a;
+                self.subtract = true;
+                self.half_carry = true;
+            }
             0x30 => {
                 if !self.carry {
                     self.instr_jr();
+                    cycles += 1;
                 } else {
-                    self.pc += 1;
+                    self.pc = self.pc.wrapping_add(1);
                 }
             }
             0x31 => {
                 self.sp = self.read_mem16(self.pc);
-                self.pc += 2;
+                self.pc = self.pc.wrapping_add(2);
             }
+            0x32 => {
+                self.write_mem(self.hl(), self.a);
+                self.set_hl(self.hl().wrapping_sub(1))
+            }
+            0x33 => self.sp = self.sp.wrapping_add(1),
             0x34 => {
-                let v = self.read_mem(self.hl()).wrapping_add(1);
-                self.zero = v == 0;
-                self.write_mem(self.hl(), 1);
+                let v = self.instr_inc(self.read_mem(self.hl()));
+                self.write_mem(self.hl(), v);
             }
             0x35 => {
-                let v = self.read_mem(self.hl()).wrapping_sub(1);
-                self.zero = v == 0;
-                self.write_mem(self.hl(), 1);
+                let v = self.instr_dec(self.read_mem(self.hl()));
+                self.write_mem(self.hl(), v);
             }
             0x36 => {
                 self.write_mem(self.hl(), self.read_mem(self.pc));
-                self.pc += 1;
+                self.pc = self.pc.wrapping_add(1);
+            }
+            0x37 => {
+                self.subtract = false;
+                self.half_carry = false;
+                self.carry = true;
             }
-            0x37 => self.carry = true,
             0x38 => {
                 if self.carry {
                     self.instr_jr();
+                    cycles += 1;
                 } else {
                     self.pc_inc();
                 }
             }
+            0x39 => self.instr_add16(self.sp),
             0x3a => {
                 self.a = self.read_mem(self.hl());
                 self.set_hl(self.hl().wrapping_sub(1))
             }
-            0x3c => {
-                self.a = self.a.wrapping_add(1);
-                self.zero = self.a == 0;
-            }
-            0x3d => {
-                self.a = self.a.wrapping_sub(1);
-                self.zero = self.a == 0;
-            }
+            0x3b => self.sp = self.sp.wrapping_sub(1),
+            0x3c => self.a = self.instr_inc(self.a),
+            0x3d => self.a = self.instr_dec(self.a),
             0x3e => {
                 self.a = self.read_mem(self.pc);
-                self.pc += 1;
+                self.pc = self.pc.wrapping_add(1);
+            }
+            0x3f => {
+                self.subtract = false;
+                self.half_carry = false;
+                self.carry = !self.carry;
             }
-            0x3f => self.carry = !self.carry,
+            0x40 => {}
             0x41 => self.b = self.c,
             0x42 => self.b = self.d,
+            0x43 => self.b = self.e,
+            0x44 => self.b = self.h,
+            0x45 => self.b = self.l,
             0x46 => self.b = self.read_mem(self.hl()),
             0x47 => self.b = self.a,
+            0x48 => self.c = self.b,
+            0x49 => {}
             0x4a => self.c = self.d,
+            0x4b => self.c = self.e,
+            0x4c => self.c = self.h,
+            0x4d => self.c = self.l,
+            0x4e => self.c = self.read_mem(self.hl()),
             0x4f => self.c = self.a,
             0x50 => self.d = self.b,
             0x51 => self.d = self.c,
+            0x52 => {}
+            0x53 => self.d = self.e,
             0x54 => self.d = self.h,
+            0x55 => self.d = self.l,
             0x56 => self.d = self.read_mem(self.hl()),
             0x57 => self.d = self.a,
+            0x58 => self.e = self.b,
+            0x59 => self.e = self.c,
+            0x5a => self.e = self.d,
+            0x5b => {}
+            0x5c => self.e = self.h,
             0x5d => self.e = self.l,
             0x5e => self.e = self.read_mem(self.hl()),
             0x5f => self.e = self.a,
+            0x60 => self.h = self.b,
+            0x61 => self.h = self.c,
             0x62 => self.h = self.d,
+            0x63 => self.h = self.e,
+            0x64 => {}
+            0x65 => self.h = self.l,
+            0x66 => self.h = self.read_mem(self.hl()),
             0x67 => self.h = self.a,
             0x68 => self.l = self.b,
+            0x69 => self.l = self.c,
+            0x6a => self.l = self.d,
             0x6b => self.l = self.e,
+            0x6c => self.l = self.h,
+            0x6d => {}
             0x6e => self.l = self.read_mem(self.hl()),
             0x6f => self.l = self.a,
             0x70 => self.write_mem(self.hl(), self.b),
@@ -247,7 +348,13 @@ impl CPU {
             0x73 => self.write_mem(self.hl(), self.e),
             0x74 => self.write_mem(self.hl(), self.h),
             0x75 => self.write_mem(self.hl(), self.l),
-            0x76 => {}
+            0x76 => {
+                if !self.ime && self.pending_interrupts() != 0x00 {
+                    self.halt_bug = true;
+                } else {
+                    self.halted = true;
+                }
+            }
             0x77 => self.write_mem(self.hl(), self.a),
             0x78 => self.a = self.b,
             0x79 => self.a = self.c,
@@ -256,34 +363,81 @@ impl CPU {
             0x7c => self.a = self.h,
             0x7d => self.a = self.l,
             0x7e => self.a = self.read_mem(self.hl()),
+            0x7f => {}
             0x80 => self.instr_add(self.b),
             0x81 => self.instr_add(self.c),
             0x82 => self.instr_add(self.d),
             0x83 => self.instr_add(self.e),
+            0x84 => self.instr_add(self.h),
             0x85 => self.instr_add(self.l),
+            0x86 => self.instr_add(self.read_mem(self.hl())),
             0x87 => self.instr_add(self.a),
             0x88 => self.instr_adc(self.b),
+            0x89 => self.instr_adc(self.c),
+            0x8a => self.instr_adc(self.d),
+            0x8b => self.instr_adc(self.e),
+            0x8c => self.instr_adc(self.h),
+            0x8d => self.instr_adc(self.l),
+            0x8e => self.instr_adc(self.read_mem(self.hl())),
+            0x8f => self.instr_adc(self.a),
+            0x90 => self.instr_sub(self.b),
+            0x91 => self.instr_sub(self.c),
+            0x92 => self.instr_sub(self.d),
+            0x93 => self.instr_sub(self.e),
+            0x94 => self.instr_sub(self.h),
+            0x95 => self.instr_sub(self.l),
+            0x96 => self.instr_sub(self.read_mem(self.hl())),
+            0x97 => self.instr_sub(self.a),
             0x98 => self.instr_sbc(self.b),
+            0x99 => self.instr_sbc(self.c),
+            0x9a => self.instr_sbc(self.d),
+            0x9b => self.instr_sbc(self.e),
+            0x9c => self.instr_sbc(self.h),
+            0x9d => self.instr_sbc(self.l),
+            0x9e => self.instr_sbc(self.read_mem(self.hl())),
+            0x9f => self.instr_sbc(self.a),
             0xa0 => self.instr_and(self.b),
+            0xa1 => self.instr_and(self.c),
+            0xa2 => self.instr_and(self.d),
             0xa3 => self.instr_and(self.e),
+            0xa4 => self.instr_and(self.h),
+            0xa5 => self.instr_and(self.l),
             0xa6 => self.instr_and(self.read_mem(self.hl())),
             0xa7 => self.instr_and(self.a),
             0xa8 => self.instr_xor(self.b),
+            0xa9 => self.instr_xor(self.c),
+            0xaa => self.instr_xor(self.d),
+            0xab => self.instr_xor(self.e),
+            0xac => self.instr_xor(self.h),
+            0xad => self.instr_xor(self.l),
+            0xae => self.instr_xor(self.read_mem(self.hl())),
             0xaf => {
                 self.a = 0;
                 self.zero = true;
+                self.subtract = false;
+                self.half_carry = false;
                 self.carry = false;
             }
             0xb0 => self.instr_or(self.b),
             0xb1 => self.instr_or(self.c),
             0xb2 => self.instr_or(self.d),
+            0xb3 => self.instr_or(self.e),
+            0xb4 => self.instr_or(self.h),
+            0xb5 => self.instr_or(self.l),
             0xb6 => self.instr_or(self.read_mem(self.hl())),
+            0xb7 => self.instr_or(self.a),
             0xb8 => self.instr_cp(self.b),
             0xb9 => self.instr_cp(self.c),
+            0xba => self.instr_cp(self.d),
+            0xbb => self.instr_cp(self.e),
+            0xbc => self.instr_cp(self.h),
+            0xbd => self.instr_cp(self.l),
             0xbe => self.instr_cp(self.read_mem(self.hl())),
+            0xbf => self.instr_cp(self.a),
             0xc0 => {
                 if !self.zero {
-                    self.instr_ret()
+                    self.instr_ret();
+                    cycles += 3;
                 }
             }
             0xc1 => {
@@ -292,58 +446,122 @@ impl CPU {
             }
             0xc2 => {
                 if !self.zero {
-                    self.pc = self.read_mem16(self.pc)
+                    self.pc = self.read_mem16(self.pc);
+                    cycles += 1;
                 } else {
-                    self.pc += 2;
+                    self.pc = self.pc.wrapping_add(2);
                 }
             }
             0xc3 => self.pc = self.read_mem16(self.pc),
+            0xc4 => {
+                if !self.zero {
+                    self.instr_call();
+                    cycles += 3;
+                } else {
+                    self.pc = self.pc.wrapping_add(2);
+                }
+            }
             0xc5 => self.push(self.bc()),
+            0xc6 => {
+                self.instr_add(self.read_mem(self.pc));
+                self.pc = self.pc.wrapping_add(1);
+            }
+            0xc7 => self.instr_rst(0x00),
             0xc8 => {
                 if self.zero {
                     self.instr_ret();
+                    cycles += 3;
                 }
             }
             0xc9 => self.instr_ret(),
             0xca => {
                 if self.zero {
-                    self.pc = self.read_mem16(self.pc)
+                    self.pc = self.read_mem16(self.pc);
+                    cycles += 1;
                 } else {
-                    self.pc += 2;
+                    self.pc = self.pc.wrapping_add(2);
                 }
             }
             0xcc => {
                 if self.zero {
                     self.instr_call();
+                    cycles += 3;
+                } else {
+                    self.pc = self.pc.wrapping_add(2);
                 }
             }
             0xcd => self.instr_call(),
+            0xce => {
+                self.instr_adc(self.read_mem(self.pc));
+                self.pc = self.pc.wrapping_add(1);
+            }
+            0xcf => self.instr_rst(0x08),
+            0xd0 => {
+                if !self.carry {
+                    self.instr_ret();
+                    cycles += 3;
+                }
+            }
             0xd1 => {
                 let value = self.pop();
                 self.set_de(value)
             }
             0xd2 => {
                 if !self.carry {
-                    self.pc = self.read_mem16(self.pc)
+                    self.pc = self.read_mem16(self.pc);
+                    cycles += 1;
                 } else {
-                    self.pc += 2;
+                    self.pc = self.pc.wrapping_add(2);
+                }
+            }
+            0xd4 => {
+                if !self.carry {
+                    self.instr_call();
+                    cycles += 3;
+                } else {
+                    self.pc = self.pc.wrapping_add(2);
                 }
             }
             0xd5 => self.push(self.de()),
+            0xd6 => {
+                self.instr_sub(self.read_mem(self.pc));
+                self.pc = self.pc.wrapping_add(1);
+            }
+            0xd7 => self.instr_rst(0x10),
+            0xd8 => {
+                if self.carry {
+                    self.instr_ret();
+                    cycles += 3;
+                }
+            }
             0xd9 => {
                 self.ime = true;
                 self.instr_ret();
             }
             0xda => {
                 if self.carry {
-                    self.pc = self.read_mem16(self.pc)
+                    self.pc = self.read_mem16(self.pc);
+                    cycles += 1;
                 } else {
-                    self.pc += 2;
+                    self.pc = self.pc.wrapping_add(2);
                 }
             }
+            0xdc => {
+                if self.carry {
+                    self.instr_call();
+                    cycles += 3;
+                } else {
+                    self.pc = self.pc.wrapping_add(2);
+                }
+            }
+            0xde => {
+                self.instr_sbc(self.read_mem(self.pc));
+                self.pc = self.pc.wrapping_add(1);
+            }
+            0xdf => self.instr_rst(0x18),
             0xe0 => {
                 let addr = self.read_mem(self.pc);
-                self.pc += 1;
+                self.pc = self.pc.wrapping_add(1);
                 self.write_mem(addr as u16 | 0xff00, self.a);
             }
             0xe1 => {
@@ -354,282 +572,197 @@ impl CPU {
             0xe5 => self.push(self.hl()),
             0xe6 => {
                 self.instr_and(self.read_mem(self.pc));
-                self.pc += 1;
+                self.pc = self.pc.wrapping_add(1);
             }
+            0xe7 => self.instr_rst(0x20),
+            0xe8 => self.sp = self.instr_add_sp(),
             0xe9 => self.pc = self.hl(),
             0xea => {
                 let addr = self.read_mem16(self.pc);
-                self.pc += 2;
+                self.pc = self.pc.wrapping_add(2);
                 self.write_mem(addr, self.a);
             }
+            0xee => {
+                self.instr_xor(self.read_mem(self.pc));
+                self.pc = self.pc.wrapping_add(1);
+            }
+            0xef => self.instr_rst(0x28),
             0xf0 => {
                 let addr = self.read_mem(self.pc);
-                self.pc += 1;
+                self.pc = self.pc.wrapping_add(1);
                 self.a = self.read_mem(addr as u16 | 0xff00);
             }
             0xf1 => {
                 let value = self.pop();
                 self.set_af(value);
             }
-            0xf3 => self.ime = false,
+            0xf2 => self.a = self.read_mem(self.c as u16 | 0xff00),
+            0xf3 => {
+                self.ime = false;
+                self.ime_delay = 0;
+            }
             0xf5 => self.push(self.af()),
             0xf6 => {
                 self.instr_or(self.read_mem(self.pc));
-                self.pc += 1;
+                self.pc = self.pc.wrapping_add(1);
             }
+            0xf7 => self.instr_rst(0x30),
+            0xf8 => {
+                let value = self.instr_add_sp();
+                self.set_hl(value);
+            }
+            0xf9 => self.sp = self.hl(),
             0xfa => {
                 let addr = self.read_mem16(self.pc);
-                self.pc += 2;
+                self.pc = self.pc.wrapping_add(2);
                 self.a = self.read_mem(addr);
             }
-            0xfb => self.ime = true,
+            0xfb => self.ime_delay = 2,
             0xfe => {
                 let addr = self.pc_inc();
                 self.instr_cp(self.read_mem(addr));
             }
+            0xff => self.instr_rst(0x38),
             0xcb => {
                 let next_pc = self.pc_inc();
-                match self.read_mem(next_pc) {
-                    0x12 => self.d = self.instr_rl(self.d),
-                    0x1b => self.e = self.instr_rr(self.e),
-                    0x23 => self.e = self.instr_sla(self.e),
-                    0x2a => self.e = self.instr_sra(self.e),
-                    0x30 => self.b = self.instr_swap(self.b),
-                    0x31 => self.c = self.instr_swap(self.c),
-                    0x32 => self.d = self.instr_swap(self.d),
-                    0x33 => self.e = self.instr_swap(self.e),
-                    0x34 => self.h = self.instr_swap(self.h),
-                    0x35 => self.l = self.instr_swap(self.l),
-                    0x36 => {
-                        let v = self.instr_swap(self.read_mem(self.hl()));
-                        self.write_mem(self.hl(), v);
+                let opcode = self.read_mem(next_pc);
+                let reg = opcode & 0x07;
+                let bit = (opcode >> 3) & 0x07;
+                let value = self.get_reg8(reg);
+                if reg != 6 {
+                    cycles += 1;
+                } else if (opcode >> 6) == 1 {
+                    cycles += 2;
+                } else {
+                    cycles += 3;
+                }
+                match opcode >> 6 {
+                    0 => {
+                        let result = match bit {
+                            0 => self.instr_rlc(value),
+                            1 => self.instr_rrc(value),
+                            2 => self.instr_rl(value),
+                            3 => self.instr_rr(value),
+                            4 => self.instr_sla(value),
+                            5 => self.instr_sra(value),
+                            6 => self.instr_swap(value),
+                            _ => self.instr_srl(value),
+                        };
+                        self.set_reg8(reg, result);
                     }
-                    0x37 => self.a = self.instr_swap(self.a),
-                    0x3f => self.a = self.instr_srl(self.a),
-                    0x46 => self.zero = (self.read_mem(self.hl()) & (1 << 0)) == 0,
-                    0x47 => self.zero = (self.a & (1 << 0)) == 0,
-                    0x4e => self.zero = (self.read_mem(self.hl()) & (1 << 1)) == 0,
-                    0x4f => self.zero = (self.a & (1 << 1)) == 0,
-                    0x50 => self.zero = (self.b & (1 << 2)) == 0,
-                    0x51 => self.zero = (self.c & (1 << 2)) == 0,
-                    0x52 => self.zero = (self.d & (1 << 2)) == 0,
-                    0x53 => self.zero = (self.e & (1 << 2)) == 0,
-                    0x54 => self.zero = (self.h & (1 << 2)) == 0,
-                    0x55 => self.zero = (self.l & (1 << 2)) == 0,
-                    0x56 => self.zero = (self.read_mem(self.hl()) & (1 << 2)) == 0,
-                    0x57 => self.zero = (self.a & (1 << 2)) == 0,
-                    0x58 => self.zero = (self.b & (1 << 3)) == 0,
-                    0x59 => self.zero = (self.c & (1 << 3)) == 0,
-                    0x5a => self.zero = (self.d & (1 << 3)) == 0,
-                    0x5b => self.zero = (self.e & (1 << 3)) == 0,
-                    0x5c => self.zero = (self.h & (1 << 3)) == 0,
-                    0x5d => self.zero = (self.l & (1 << 3)) == 0,
-                    0x5e => self.zero = (self.read_mem(self.hl()) & (1 << 3)) == 0,
-                    0x5f => self.zero = (self.a & (1 << 3)) == 0,
-                    0x66 => self.zero = (self.read_mem(self.hl()) & (1 << 4)) == 0,
-                    0x6f => self.zero = (self.a & (1 << 5)) == 0,
-                    0x70 => self.zero = (self.b & (1 << 6)) == 0,
-                    0x71 => self.zero = (self.c & (1 << 6)) == 0,
-                    0x72 => self.zero = (self.d & (1 << 6)) == 0,
-                    0x73 => self.zero = (self.e & (1 << 6)) == 0,
-                    0x74 => self.zero = (self.h & (1 << 6)) == 0,
-                    0x75 => self.zero = (self.l & (1 << 6)) == 0,
-                    0x76 => self.zero = (self.read_mem(self.hl()) & (1 << 6)) == 0,
-                    0x77 => self.zero = (self.a & (1 << 6)) == 0,
-                    0x78 => self.zero = (self.b & (1 << 7)) == 0,
-                    0x79 => self.zero = (self.c & (1 << 7)) == 0,
-                    0x7a => self.zero = (self.d & (1 << 7)) == 0,
-                    0x7b => self.zero = (self.e & (1 << 7)) == 0,
-                    0x7c => self.zero = (self.h & (1 << 7)) == 0,
-                    0x7d => self.zero = (self.l & (1 << 7)) == 0,
-                    0x7e => self.zero = (self.read_mem(self.hl()) & (1 << 7)) == 0,
-                    0x7f => self.zero = (self.a & (1 << 7)) == 0,
-                    0x80 => self.b &= !(1 << 0),
-                    0x81 => self.c &= !(1 << 0),
-                    0x82 => self.d &= !(1 << 0),
-                    0x83 => self.e &= !(1 << 0),
-                    0x84 => self.h &= !(1 << 0),
-                    0x85 => self.l &= !(1 << 0),
-                    0x86 => self.write_mem(self.hl(), self.read_mem(self.hl()) & !(1 << 0)),
-                    0x87 => self.a &= !(1 << 0),
-                    0x88 => self.b &= !(1 << 1),
-                    0x89 => self.c &= !(1 << 1),
-                    0x8a => self.d &= !(1 << 1),
-                    0x8b => self.e &= !(1 << 1),
-                    0x8c => self.h &= !(1 << 1),
-                    0x8d => self.l &= !(1 << 1),
-                    0x8e => self.write_mem(self.hl(), self.read_mem(self.hl()) & !(1 << 1)),
-                    0x8f => self.a &= !(1 << 1),
-                    0x90 => self.b &= !(1 << 2),
-                    0x91 => self.c &= !(1 << 2),
-                    0x92 => self.d &= !(1 << 2),
-                    0x93 => self.e &= !(1 << 2),
-                    0x94 => self.h &= !(1 << 2),
-                    0x95 => self.l &= !(1 << 2),
-                    0x96 => self.write_mem(self.hl(), self.read_mem(self.hl()) & !(1 << 2)),
-                    0x97 => self.a &= !(1 << 2),
-                    0xa2 => self.d &= !(1 << 4),
-                    0xa6 => self.write_mem(self.hl(), self.read_mem(self.hl()) & !(1 << 4)),
-                    0xae => self.write_mem(self.hl(), self.read_mem(self.hl()) & !(1 << 5)),
-                    0xaf => self.a &= !(1 << 5),
-                    0xd0 => self.b |= 1 << 2,
-                    0xd1 => self.c |= 1 << 2,
-                    0xd2 => self.d |= 1 << 2,
-                    0xd3 => self.e |= 1 << 2,
-                    0xd4 => self.h |= 1 << 2,
-                    0xd5 => self.l |= 1 << 2,
-                    0xd6 => self.write_mem(self.hl(), self.read_mem(self.hl()) | (1 << 2)),
-                    0xd7 => self.a |= 1 << 2,
-                    0xd8 => self.b |= 1 << 3,
-                    0xd9 => self.c |= 1 << 3,
-                    0xda => self.d |= 1 << 3,
-                    0xdb => self.e |= 1 << 3,
-                    0xdc => self.h |= 1 << 3,
-                    0xdd => self.l |= 1 << 3,
-                    0xde => self.write_mem(self.hl(), self.read_mem(self.hl()) | (1 << 3)),
-                    0xdf => self.a |= 1 << 3,
-                    _ => panic!(
-                        "Unknown opcode: {:04x}:cb:{:02x}",
-                        pc,
-                        self.read_mem(next_pc)
-                    ),
+                    1 => self.instr_bit(bit, value),
+                    2 => self.set_reg8(reg, value & !(1 << bit)),
+                    _ => self.set_reg8(reg, value | (1 << bit)),
                 }
             }
-            _ => panic!("Unknown opcode: {:04x}:{:02x}", pc, self.read_mem(pc)),
+            // 0xd3, 0xdb, 0xdd, 0xe3, 0xe4, 0xeb, 0xec, 0xed, 0xf4, 0xfc and 0xfd lock up the CPU.
+            _ => self.locked = true,
         }
 
-        self.video.step();
-        if self.video.vblank_interrupt {
-            self.video.vblank_interrupt = false;
-            self.reg_if |= 0x01;
+        self.bus.tick(cycles);
+
+        // EI only takes effect after the instruction following it.
+        if self.ime_delay > 0 {
+            self.ime_delay -= 1;
+            if self.ime_delay == 0 {
+                self.ime = true;
+            }
         }
 
-        if self.ime && (self.reg_if & self.reg_ie) != 0x00 {
-            if (self.reg_if & self.reg_ie & 0x01) == 0x01 {
-                self.reg_if &= !0x01;
-                self.ime = false;
-                self.push(self.pc);
-                self.pc = 0x0040;
+        cycles += self.handle_interrupts();
+        return cycles;
+    }
+
+    // While halted the peripherals keep running until an enabled interrupt is requested,
+    // while stopped only a joypad press wakes the CPU up again.
+    fn step_halted(&mut self) -> u32 {
+        let mut cycles = 1;
+        self.bus.tick(cycles);
+        if self.stopped {
+            if self.bus.wake_from_stop() {
+                self.stopped = false;
             }
+            return cycles;
+        }
+        if self.pending_interrupts() != 0x00 {
+            self.halted = false;
         }
+        cycles += self.handle_interrupts();
+        return cycles;
     }
 
-    fn read_mem(&self, addr: u16) -> u8 {
-        if addr < 0x4000 {
-            return self.rom.get(addr as usize).copied().unwrap_or(0);
-        } else if addr < 0x8000 {
-            return self
-                .rom
-                .get(addr as usize - 0x4000 + self.rombank_offset)
-                .copied()
-                .unwrap_or(0);
-        } else if addr < 0xa000 {
-            return self.video.vram[addr as usize - 0x8000];
-        } else if addr >= 0xc000 && addr < 0xe000 {
-            return self.wram[addr as usize - 0xc000];
-        } else if addr == 0xff00 {
-            return 0xff;
-        } else if addr == 0xff04 {
-            return (self.start_time.elapsed().as_nanos() & 0xff) as u8;
-        } else if addr == 0xff0f {
-            return self.reg_if;
-        } else if addr == 0xff25 {
+    // Service the highest priority pending interrupt: VBlank, STAT, Timer, Serial, Joypad.
+    fn handle_interrupts(&mut self) -> u32 {
+        let pending = self.pending_interrupts();
+        if !self.ime || pending == 0x00 {
             return 0;
-        } else if addr == 0xff40 {
-            return self.video.lcdc;
-        } else if addr == 0xff41 {
-            return self.video.stat;
-        } else if addr == 0xff44 {
-            return self.video.ly;
-        } else if addr >= 0xff80 && addr < 0xffff {
-            return self.hram[addr as usize - 0xff80];
-        } else if addr == 0xffff {
-            return self.reg_ie;
         }
-        panic!("Read unknown addr: {:04x}", addr);
-        return 0;
+        let bit = pending.trailing_zeros() as u16;
+        self.write_mem(0xff0f, self.read_mem(0xff0f) & !(1 << bit));
+        self.ime = false;
+        self.halted = false;
+        let mut return_address = self.pc;
+        if self.halt_bug {
+            // EI followed by HALT with an interrupt pending, the handler returns to the HALT.
+            self.halt_bug = false;
+            return_address = self.pc.wrapping_sub(1);
+        }
+        self.push(return_address);
+        self.pc = 0x0040 + bit * 8;
+        self.bus.tick(5);
+        return 5;
+    }
+
+    fn pending_interrupts(&self) -> u8 {
+        return self.read_mem(0xff0f) & self.read_mem(0xffff) & 0x1f;
+    }
+
+    fn read_mem(&self, addr: u16) -> u8 {
+        return self.bus.read(addr);
     }
 
     fn write_mem(&mut self, addr: u16, value: u8) {
-        if addr == 0x2000 {
-            self.rombank_offset = value as usize * 0x4000;
-        } else if addr >= 0xc000 && addr < 0xe000 {
-            self.wram[addr as usize - 0xc000] = value;
-        } else if addr >= 0x8000 && addr < 0xa000 {
-            self.video.vram[addr as usize - 0x8000] = value;
-        } else if addr >= 0xff80 && addr < 0xffff {
-            self.hram[addr as usize - 0xff80] = value;
-        } else if addr == 0xff00 {
-        } else if addr == 0xff01 {
-        } else if addr == 0xff02 {
-        } else if addr == 0xff06 {
-        } else if addr == 0xff07 {
-        } else if addr == 0xff0f {
-            self.reg_if = value;
-        } else if addr == 0xff10 {
-        } else if addr == 0xff12 {
-        } else if addr == 0xff14 {
-        } else if addr == 0xff17 {
-        } else if addr == 0xff19 {
-        } else if addr == 0xff1a {
-        } else if addr == 0xff1c {
-        } else if addr == 0xff21 {
-        } else if addr == 0xff23 {
-        } else if addr == 0xff24 {
-        } else if addr == 0xff25 {
-        } else if addr == 0xff26 {
-        } else if addr == 0xff40 {
-            self.video.lcdc = value;
-        } else if addr == 0xff41 {
-            self.video.stat = value;
-        } else if addr == 0xff42 {
-        } else if addr == 0xff43 {
-        } else if addr == 0xff46 {
-            for n in 0..40 * 4 {
-                self.video.oam[n] = self.read_mem((value as u16) << 8 | n as u16);
-            }
-        } else if addr == 0xff47 {
-        } else if addr == 0xff48 {
-        } else if addr == 0xff49 {
-        } else if addr == 0xff4a {
-        } else if addr == 0xff4b {
-        } else if addr == 0xffff {
-            self.reg_ie = value;
-        } else {
-            panic!("Write unknown addr: {:04x}", addr);
-        }
+        self.bus.write(addr, value);
     }
 
     fn read_mem16(&self, addr: u16) -> u16 {
         let low = self.read_mem(addr);
-        let high = self.read_mem(addr + 1);
+        let high = self.read_mem(addr.wrapping_add(1));
         return low as u16 | ((high as u16) << 8);
     }
 
     fn write_mem16(&mut self, addr: u16, value: u16) {
         self.write_mem(addr, (value & 0xff) as u8);
-        self.write_mem(addr + 1, (value >> 8) as u8);
+        self.write_mem(addr.wrapping_add(1), (value >> 8) as u8);
     }
 
     fn pc_inc(&mut self) -> u16 {
         let result = self.pc;
-        self.pc += 1;
+        self.pc = self.pc.wrapping_add(1);
         return result;
     }
 
     fn set_af(&mut self, value: u16) {
-        self.a = (value & 0xff) as u8;
-        self.zero = (value & 0x100) == 0x100;
-        self.carry = (value & 0x200) == 0x200;
+        self.a = (value >> 8) as u8;
+        self.zero = (value & 0x80) == 0x80;
+        self.subtract = (value & 0x40) == 0x40;
+        self.half_carry = (value & 0x20) == 0x20;
+        self.carry = (value & 0x10) == 0x10;
     }
 
     fn af(&self) -> u16 {
-        let mut result = self.a as u16;
+        let mut result = (self.a as u16) << 8;
         if self.zero {
-            result |= 0x100;
+            result |= 0x80;
+        }
+        if self.subtract {
+            result |= 0x40;
+        }
+        if self.half_carry {
+            result |= 0x20;
         }
         if self.carry {
-            result |= 0x200;
+            result |= 0x10;
         }
         return result;
     }
@@ -661,14 +794,41 @@ impl CPU {
         return ((self.h as u16) << 8) | self.l as u16;
     }
 
+    // Register encoding used by the opcode table: B, C, D, E, H, L, (HL), A
+    fn get_reg8(&self, index: u8) -> u8 {
+        return match index {
+            0 => self.b,
+            1 => self.c,
+            2 => self.d,
+            3 => self.e,
+            4 => self.h,
+            5 => self.l,
+            6 => self.read_mem(self.hl()),
+            _ => self.a,
+        };
+    }
+
+    fn set_reg8(&mut self, index: u8, value: u8) {
+        match index {
+            0 => self.b = value,
+            1 => self.c = value,
+            2 => self.d = value,
+            3 => self.e = value,
+            4 => self.h = value,
+            5 => self.l = value,
+            6 => self.write_mem(self.hl(), value),
+            _ => self.a = value,
+        }
+    }
+
     fn push(&mut self, value: u16) {
-        self.sp -= 2;
+        self.sp = self.sp.wrapping_sub(2);
         self.write_mem16(self.sp, value);
     }
 
     fn pop(&mut self) -> u16 {
         let result = self.read_mem16(self.sp);
-        self.sp += 2;
+        self.sp = self.sp.wrapping_add(2);
         return result;
     }
 
@@ -681,75 +841,177 @@ impl CPU {
         self.pc = self.pc.wrapping_add(offset);
     }
 
+    fn instr_inc(&mut self, value: u8) -> u8 {
+        let result = value.wrapping_add(1);
+        self.zero = result == 0;
+        self.subtract = false;
+        self.half_carry = (value & 0x0f) == 0x0f;
+        return result;
+    }
+
+    fn instr_dec(&mut self, value: u8) -> u8 {
+        let result = value.wrapping_sub(1);
+        self.zero = result == 0;
+        self.subtract = true;
+        self.half_carry = (value & 0x0f) == 0x00;
+        return result;
+    }
+
+    fn instr_add16(&mut self, value: u16) {
+        let hl = self.hl();
+        let result = hl as u32 + value as u32;
+        self.set_hl((result & 0xffff) as u16);
+        self.subtract = false;
+        self.half_carry = (hl & 0x0fff) + (value & 0x0fff) > 0x0fff;
+        self.carry = result > 0xffff;
+    }
+
+    fn instr_add_sp(&mut self) -> u16 {
+        let addr = self.pc_inc();
+        let value = self.read_mem(addr);
+        let mut offset = value as u16;
+        if (offset & 0x80) == 0x80 {
+            offset |= 0xff00;
+        }
+        self.zero = false;
+        self.subtract = false;
+        self.half_carry = (self.sp & 0x0f) + (value as u16 & 0x0f) > 0x0f;
+        self.carry = (self.sp & 0xff) + value as u16 > 0xff;
+        return self.sp.wrapping_add(offset);
+    }
+
+    fn instr_daa(&mut self) {
+        let mut correction = 0;
+        if self.carry || (!self.subtract && self.a > 0x99) {
+            correction |= 0x60;
+            self.carry = true;
+        }
+        if self.half_carry || (!self.subtract && (self.a & 0x0f) > 0x09) {
+            correction |= 0x06;
+        }
+        if self.subtract {
+            self.a = self.a.wrapping_sub(correction);
+        } else {
+            self.a = self.a.wrapping_add(correction);
+        }
+        self.zero = self.a == 0;
+        self.half_carry = false;
+    }
+
     fn instr_add(&mut self, value: u8) {
         let result = self.a as u16 + value as u16;
+        self.half_carry = (self.a & 0x0f) + (value & 0x0f) > 0x0f;
         self.a = (result & 0xff) as u8;
         self.zero = self.a == 0;
+        self.subtract = false;
         self.carry = result > 0xff;
     }
 
     fn instr_adc(&mut self, value: u8) {
-        let mut result = self.a as u16 + value as u16;
-        if self.carry {
-            result += 1;
-        }
+        let carry = if self.carry { 1 } else { 0 };
+        let result = self.a as u16 + value as u16 + carry as u16;
+        self.half_carry = (self.a & 0x0f) + (value & 0x0f) + carry > 0x0f;
         self.a = (result & 0xff) as u8;
         self.zero = self.a == 0;
+        self.subtract = false;
         self.carry = result > 0xff;
     }
 
     fn instr_sub(&mut self, value: u8) {
         let result = (self.a as u16).wrapping_sub(value as u16);
+        self.half_carry = (self.a & 0x0f) < (value & 0x0f);
         self.a = (result & 0xff) as u8;
         self.zero = self.a == 0;
+        self.subtract = true;
         self.carry = result > 0xff;
     }
 
     fn instr_sbc(&mut self, value: u8) {
-        let mut result = (self.a as u16).wrapping_sub(value as u16);
-        if self.carry {
-            result = result.wrapping_sub(1);
-        }
+        let carry = if self.carry { 1 } else { 0 };
+        let result = (self.a as u16)
+            .wrapping_sub(value as u16)
+            .wrapping_sub(carry as u16);
+        self.half_carry = (self.a & 0x0f) < (value & 0x0f) + carry;
         self.a = (result & 0xff) as u8;
         self.zero = self.a == 0;
+        self.subtract = true;
         self.carry = result > 0xff;
     }
 
     fn instr_and(&mut self, value: u8) {
         self.a &= value;
         self.zero = self.a == 0;
+        self.subtract = false;
+        self.half_carry = true;
         self.carry = false;
     }
 
     fn instr_or(&mut self, value: u8) {
         self.a |= value;
         self.zero = self.a == 0;
+        self.subtract = false;
+        self.half_carry = false;
         self.carry = false;
     }
 
     fn instr_xor(&mut self, value: u8) {
         self.a ^= value;
         self.zero = self.a == 0;
+        self.subtract = false;
+        self.half_carry = false;
         self.carry = false;
     }
 
     fn instr_cp(&mut self, value: u8) {
         self.zero = value == self.a;
+        self.subtract = true;
+        self.half_carry = (self.a & 0x0f) < (value & 0x0f);
         self.carry = value > self.a;
     }
 
+    fn instr_bit(&mut self, bit: u8, value: u8) {
+        self.zero = (value & (1 << bit)) == 0;
+        self.subtract = false;
+        self.half_carry = true;
+    }
+
     fn instr_call(&mut self) {
-        self.push(self.pc + 2);
+        self.push(self.pc.wrapping_add(2));
         self.pc = self.read_mem16(self.pc);
     }
 
+    fn instr_rst(&mut self, addr: u16) {
+        self.push(self.pc);
+        self.pc = addr;
+    }
+
     fn instr_ret(&mut self) {
         self.pc = self.pop();
     }
 
+    fn instr_rlc(&mut self, value: u8) -> u8 {
+        let result = value.rotate_left(1);
+        self.carry = (value & 0x80) == 0x80;
+        self.zero = result == 0;
+        self.subtract = false;
+        self.half_carry = false;
+        return result;
+    }
+
+    fn instr_rrc(&mut self, value: u8) -> u8 {
+        let result = value.rotate_right(1);
+        self.carry = (value & 0x01) == 0x01;
+        self.zero = result == 0;
+        self.subtract = false;
+        self.half_carry = false;
+        return result;
+    }
+
     fn instr_sla(&mut self, value: u8) -> u8 {
         self.carry = (value & 0x80) == 0x80;
         self.zero = (value & 0x7f) << 1 == 0;
+        self.subtract = false;
+        self.half_carry = false;
         return (value & 0x7f) << 1;
     }
 
@@ -757,6 +1019,8 @@ impl CPU {
         let result = (value >> 1) | (value & 0x80);
         self.carry = (value & 0x01) == 0x01;
         self.zero = result == 0;
+        self.subtract = false;
+        self.half_carry = false;
         return result;
     }
 
@@ -764,6 +1028,8 @@ impl CPU {
         let result = value >> 1;
         self.carry = (value & 0x01) == 0x01;
         self.zero = result == 0;
+        self.subtract = false;
+        self.half_carry = false;
         return result;
     }
 
@@ -774,6 +1040,8 @@ impl CPU {
         }
         self.carry = (value & 0x80) == 0x80;
         self.zero = result == 0;
+        self.subtract = false;
+        self.half_carry = false;
         return result;
     }
 
@@ -784,11 +1052,16 @@ impl CPU {
         }
         self.carry = (value & 0x01) == 0x01;
         self.zero = result == 0;
+        self.subtract = false;
+        self.half_carry = false;
         return result;
     }
 
     fn instr_swap(&mut self, value: u8) -> u8 {
         self.zero = value == 0;
+        self.subtract = false;
+        self.half_carry = false;
+        self.carry = false;
         return value >> 4 | (value & 0x0f) << 4;
     }
 }
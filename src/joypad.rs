@@ -0,0 +1,55 @@
+pub struct Joypad {
+    select: u8,
+    buttons: u8,
+
+    pub interrupt: bool,
+}
+
+impl Joypad {
+    pub fn new() -> Joypad {
+        return Joypad {
+            select: 0x30,
+            buttons: 0,
+
+            interrupt: false,
+        };
+    }
+
+    // P1 is active low: bit 4 low selects the directions, bit 5 low selects the action buttons.
+    pub fn read(&self) -> u8 {
+        let mut result = 0xc0 | self.select | 0x0f;
+        if (self.select & 0x10) == 0x00 {
+            result &= !(self.buttons & 0x0f);
+        }
+        if (self.select & 0x20) == 0x00 {
+            result &= !(self.buttons >> 4);
+        }
+        return result;
+    }
+
+    pub fn write(&mut self, value: u8) {
+        let old = self.read();
+        self.select = value & 0x30;
+        self.check_interrupt(old);
+    }
+
+    // Pressed buttons: right, left, up, down, A, B, select, start from bit 0 to 7.
+    pub fn set_buttons(&mut self, buttons: u8) {
+        let old = self.read();
+        self.buttons = buttons;
+        self.check_interrupt(old);
+    }
+
+    // The joypad interrupt is requested when any input line goes from high to low.
+    fn check_interrupt(&mut self, old: u8) {
+        if (old & !self.read() & 0x0f) != 0x00 {
+            self.interrupt = true;
+        }
+    }
+}
+
+impl Default for Joypad {
+    fn default() -> Joypad {
+        return Joypad::new();
+    }
+}
@@ -0,0 +1,87 @@
+pub mod apu;
+pub mod bus;
+pub mod cartridge;
+pub mod cpu;
+pub mod joypad;
+pub mod mbc;
+pub mod serial;
+pub mod timer;
+pub mod video;
+
+use bus::SystemBus;
+use cartridge::Cartridge;
+use cpu::{Model, CPU};
+use std::path::Path;
+
+// A complete Game Boy, without any window or audio output of its own.
+pub struct Emulator {
+    cpu: CPU<SystemBus>,
+}
+
+impl Emulator {
+    // Without a boot ROM emulation starts in the state the boot ROM leaves behind.
+    pub fn new(cartridge: Cartridge, model: Model, boot_rom: Vec<u8>) -> Emulator {
+        let has_boot_rom = !boot_rom.is_empty();
+        let bus = SystemBus::new(cartridge, boot_rom, model);
+        let mut cpu = CPU::new(bus, model);
+        if has_boot_rom {
+            cpu.reset_for_boot_rom();
+        }
+        return Emulator { cpu: cpu };
+    }
+
+    // Load a ROM file and run it as a DMG. Battery backed RAM is loaded from and saved to
+    // save_filename when given, without it nothing is persisted.
+    pub fn load_rom(filename: &str, save_filename: Option<&Path>) -> Result<Emulator, String> {
+        let mut cartridge = Cartridge::load(filename, mbc::RtcMode::Host)?;
+        if let Some(save_filename) = save_filename {
+            cartridge.set_save_file(save_filename.to_path_buf())?;
+        }
+        return Ok(Emulator::new(cartridge, Model::DMG, Vec::new()));
+    }
+
+    // Run until the next frame is complete.
+    pub fn run_frame(&mut self) {
+        self.cpu.bus.apu.clear_samples();
+        self.cpu.bus.video.frame_ready = false;
+        while !self.cpu.bus.video.frame_ready {
+            self.cpu.step();
+        }
+    }
+
+    // Pressed buttons: right, left, up, down, A, B, select, start from bit 0 to 7.
+    pub fn set_buttons(&mut self, buttons: u8) {
+        self.cpu.bus.joypad.set_buttons(buttons);
+    }
+
+    // The last completed frame, one shade from 0 (white) to 3 (black) per pixel,
+    // video::SCREEN_WIDTH by video::SCREEN_HEIGHT.
+    pub fn framebuffer(&self) -> &[u8] {
+        return &self.cpu.bus.video.framebuffer;
+    }
+
+    // Interleaved stereo samples at apu::SAMPLE_RATE produced during the last run_frame().
+    // Not implemented yet: the sound channels are not emulated, so this is always silence.
+    pub fn audio_samples(&self) -> &[i16] {
+        return &self.cpu.bus.apu.samples;
+    }
+
+    // Accelerometer input for tilt carts, -1.0 to 1.0 on each axis.
+    pub fn set_tilt(&mut self, x: f32, y: f32) {
+        self.cpu.bus.cartridge.set_tilt(x, y);
+    }
+
+    // Bytes sent out over the serial port so far.
+    pub fn serial_output(&self) -> &[u8] {
+        return &self.cpu.bus.serial.output;
+    }
+
+    pub fn rumble(&self) -> bool {
+        return self.cpu.bus.cartridge.rumble();
+    }
+
+    // Write battery backed RAM to the save file, if the cartridge has one.
+    pub fn save(&mut self) {
+        self.cpu.bus.save();
+    }
+}
@@ -1,10 +1,277 @@
-mod cpu;
-mod video;
+#[cfg(feature = "sdl")]
+mod window;
+
+use not_so_gb::cartridge::Cartridge;
+use not_so_gb::cpu::Model;
+use not_so_gb::video::{SCREEN_HEIGHT, SCREEN_WIDTH, SHADES};
+use not_so_gb::{mbc, Emulator};
+use std::path::{Path, PathBuf};
+use std::process;
+
+const USAGE: &str = "Usage: not-so-gb [options] <rom>
+
+Options:
+  --scale <n>           Scale the window by an integer factor (default 1)
+  --boot-rom <file>     Start from the given boot ROM instead of the post-boot state
+  --model <dmg|cgb>     Hardware model to emulate (default dmg, CGB hardware is incomplete)
+  --rtc <emulated|host> Run the cartridge clock from emulated or host time (default host)
+  --camera-image <file> PGM image the Pocket Camera sensor sees
+  --save-dir <dir>      Store .sav files here instead of next to the ROM
+  --headless            Run without a window, needs --frames
+  --frames <n>          Stop after n frames
+  --until-serial <text> Stop once the serial output contains text, exit with 1 if it has not
+                        by the time the window is closed or the frame limit is reached
+  --dump-frame <file>   Write the last frame to a PGM image on exit
+  --info                Print the cartridge header and exit
+  -h, --help            Show this help
+";
+
+fn print_usage() {
+    eprint!("{}", USAGE);
+}
+
+fn print_info(cartridge: &Cartridge) {
+    let valid = |valid: bool| if valid { "ok" } else { "INVALID" };
+    println!("Title:           {}", cartridge.title);
+    println!("Manufacturer:    {}", cartridge.manufacturer_code);
+    println!("CGB flag:        {:02x}", cartridge.cgb_flag);
+    println!("SGB flag:        {:02x}", cartridge.sgb_flag);
+    println!(
+        "Cartridge type:  {:02x} ({}, {:?})",
+        cartridge.cartridge_type,
+        cartridge.cartridge_type_name(),
+        cartridge.mbc_type()
+    );
+    println!("Battery:         {}", cartridge.has_battery());
+    println!("RTC:             {}", cartridge.has_rtc());
+    println!("Rumble:          {}", cartridge.has_rumble());
+    println!("ROM size:        {} KiB", cartridge.rom_size / 1024);
+    println!("RAM size:        {} KiB", cartridge.ram_size / 1024);
+    println!("Licensee:        {}", cartridge.licensee);
+    println!("Version:         {}", cartridge.version);
+    println!("Nintendo logo:   {}", valid(cartridge.logo_valid()));
+    println!(
+        "Header checksum: {:02x} {}",
+        cartridge.header_checksum,
+        valid(cartridge.header_checksum_valid())
+    );
+    println!(
+        "Global checksum: {:04x} {}",
+        cartridge.global_checksum,
+        valid(cartridge.global_checksum_valid())
+    );
+}
+
+// Save files are named after the ROM, with a .sav extension.
+fn save_filename(rom_filename: &str, save_dir: &Option<String>) -> PathBuf {
+    let rom_path = Path::new(rom_filename);
+    let filename = rom_path.with_extension("sav");
+    return match save_dir {
+        Some(save_dir) => Path::new(save_dir).join(filename.file_name().unwrap()),
+        None => filename,
+    };
+}
+
+fn write_pgm(filename: &str, framebuffer: &[u8]) -> Result<(), String> {
+    let mut data = format!("P5\n{} {}\n255\n", SCREEN_WIDTH, SCREEN_HEIGHT).into_bytes();
+    for shade in framebuffer {
+        data.push(SHADES[*shade as usize & 0x03]);
+    }
+    return std::fs::write(filename, data).map_err(|e| format!("{}: {}", filename, e));
+}
+
+fn contains(haystack: &[u8], needle: &str) -> bool {
+    return haystack
+        .windows(needle.len().max(1))
+        .any(|window| window == needle.as_bytes());
+}
+
+#[cfg(feature = "sdl")]
+fn open_window(headless: bool, scale: u32) -> Option<window::Window> {
+    if headless {
+        return None;
+    }
+    return match window::Window::new(scale) {
+        Ok(window) => Some(window),
+        Err(message) => fail(&message),
+    };
+}
+
+#[cfg(not(feature = "sdl"))]
+fn open_window(headless: bool, _scale: u32) -> Option<()> {
+    if !headless {
+        fail("built without SDL support, use --headless");
+    }
+    return None;
+}
+
+// Show the last frame and pass on the input, returns false when the window was closed.
+#[cfg(feature = "sdl")]
+fn update_window(window: &mut Option<window::Window>, emulator: &mut Emulator) -> bool {
+    if let Some(window) = window {
+        window.present(emulator.framebuffer(), emulator.rumble());
+        window.poll_events();
+        if window.quit {
+            return false;
+        }
+        emulator.set_buttons(window.buttons);
+        emulator.set_tilt(window.tilt_x, window.tilt_y);
+    }
+    return true;
+}
+
+#[cfg(not(feature = "sdl"))]
+fn update_window(_window: &mut Option<()>, _emulator: &mut Emulator) -> bool {
+    return true;
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("not-so-gb: {}", message);
+    process::exit(1);
+}
+
+fn option_value(args: &mut impl Iterator<Item = String>, option: &str) -> String {
+    return match args.next() {
+        Some(value) => value,
+        None => fail(&format!("missing value for {}", option)),
+    };
+}
 
 fn main() {
-    let mut cpu = cpu::CPU::new("pokered.gbc");
+    let mut rom_filename = None;
+    let mut boot_rom_filename = None;
+    let mut model = Model::DMG;
+    let mut scale = 1;
+    let mut info = false;
+    let mut rtc_mode = mbc::RtcMode::Host;
+    let mut save_dir = None;
+    let mut camera_image = None;
+    let mut headless = false;
+    let mut frame_limit = None;
+    let mut until_serial = None;
+    let mut dump_frame = None;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--scale" => {
+                let value = option_value(&mut args, &arg);
+                scale = match value.parse::<u32>() {
+                    Ok(scale) if scale > 0 => scale,
+                    _ => fail(&format!("invalid scale: {}", value)),
+                };
+            }
+            "--boot-rom" => boot_rom_filename = Some(option_value(&mut args, &arg)),
+            "--model" => {
+                let value = option_value(&mut args, &arg);
+                model = match value.as_str() {
+                    "dmg" => Model::DMG,
+                    "cgb" => Model::CGB,
+                    _ => fail(&format!("unknown model: {}", value)),
+                };
+            }
+            "--rtc" => {
+                let value = option_value(&mut args, &arg);
+                rtc_mode = match value.as_str() {
+                    "emulated" => mbc::RtcMode::Emulated,
+                    "host" => mbc::RtcMode::Host,
+                    _ => fail(&format!("unknown RTC mode: {}", value)),
+                };
+            }
+            "--camera-image" => camera_image = Some(option_value(&mut args, &arg)),
+            "--save-dir" => save_dir = Some(option_value(&mut args, &arg)),
+            "--headless" => headless = true,
+            "--frames" => {
+                let value = option_value(&mut args, &arg);
+                frame_limit = match value.parse::<u64>() {
+                    Ok(frames) => Some(frames),
+                    _ => fail(&format!("invalid frame count: {}", value)),
+                };
+            }
+            "--until-serial" => until_serial = Some(option_value(&mut args, &arg)),
+            "--dump-frame" => dump_frame = Some(option_value(&mut args, &arg)),
+            "--info" => info = true,
+            "-h" | "--help" => {
+                print_usage();
+                return;
+            }
+            _ if arg.starts_with('-') => fail(&format!("unknown option: {}", arg)),
+            _ => rom_filename = Some(arg),
+        }
+    }
+
+    let rom_filename = match rom_filename {
+        Some(filename) => filename,
+        None => {
+            print_usage();
+            process::exit(1);
+        }
+    };
+
+    if headless && frame_limit.is_none() {
+        fail("--headless needs --frames");
+    }
+
+    let mut cartridge = match Cartridge::load(&rom_filename, rtc_mode) {
+        Ok(cartridge) => cartridge,
+        Err(message) => fail(&message),
+    };
+    if info {
+        print_info(&cartridge);
+        return;
+    }
+    if let Err(message) = cartridge.set_save_file(save_filename(&rom_filename, &save_dir)) {
+        fail(&message);
+    }
+    if let Some(filename) = camera_image {
+        match mbc::load_camera_image(&filename) {
+            Ok(image) => cartridge.set_camera_image(image),
+            Err(message) => fail(&message),
+        }
+    }
 
+    let mut boot_rom = Vec::new();
+    if let Some(filename) = boot_rom_filename {
+        boot_rom = match std::fs::read(&filename) {
+            Ok(data) => data,
+            Err(e) => fail(&format!("{}: {}", filename, e)),
+        };
+    }
+
+    let mut window = open_window(headless, scale);
+    let mut emulator = Emulator::new(cartridge, model, boot_rom);
+    let mut frames = 0;
+    let mut serial_matched = false;
     loop {
-        cpu.step();
+        if let Some(text) = &until_serial {
+            if contains(emulator.serial_output(), text) {
+                serial_matched = true;
+                break;
+            }
+        }
+        if frame_limit == Some(frames) {
+            break;
+        }
+        emulator.run_frame();
+        frames += 1;
+        if !update_window(&mut window, &mut emulator) {
+            break;
+        }
+    }
+    emulator.save();
+
+    if headless {
+        print!("{}", String::from_utf8_lossy(emulator.serial_output()));
+    }
+    if let Some(filename) = dump_frame {
+        if let Err(message) = write_pgm(&filename, emulator.framebuffer()) {
+            fail(&message);
+        }
+    }
+    if until_serial.is_some() && !serial_matched {
+        fail(&format!(
+            "serial output did not match after {} frames",
+            frames
+        ));
     }
 }
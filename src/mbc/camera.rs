@@ -0,0 +1,205 @@
+use super::Mbc;
+
+pub const IMAGE_WIDTH: usize = 128;
+pub const IMAGE_HEIGHT: usize = 112;
+
+// Time a capture takes, the real duration depends on the exposure setting.
+const CAPTURE_CLOCKS: u32 = 129920 * 4;
+
+// Pocket Camera mapper. Setting bit 4 of the RAM bank register maps the sensor registers at
+// 0xa000-0xbfff instead of RAM. A capture converts the sensor image, here a fixed grayscale
+// image, with the dither matrix in registers 0x06-0x35 and stores it as tiles in RAM bank 0.
+pub struct Camera {
+    ram_enabled: bool,
+    rom_bank: u8,
+    ram_bank: u8,
+    registers: [u8; 0x36],
+    capture_clocks: u32,
+    // 8 bit brightness per pixel, 0 is black.
+    image: Vec<u8>,
+}
+
+impl Camera {
+    pub fn new() -> Camera {
+        return Camera {
+            ram_enabled: false,
+            rom_bank: 1,
+            ram_bank: 0,
+            registers: [0; 0x36],
+            capture_clocks: 0,
+            image: vec![0x80; IMAGE_WIDTH * IMAGE_HEIGHT],
+        };
+    }
+
+    fn capture(&mut self, ram: &mut [u8]) {
+        for y in 0..IMAGE_HEIGHT {
+            for x in 0..IMAGE_WIDTH {
+                let value = self.image[y * IMAGE_WIDTH + x];
+                let matrix = 0x06 + ((y & 3) * 4 + (x & 3)) * 3;
+                let color = if value < self.registers[matrix] {
+                    3
+                } else if value < self.registers[matrix + 1] {
+                    2
+                } else if value < self.registers[matrix + 2] {
+                    1
+                } else {
+                    0
+                };
+                let tile = (y / 8) * (IMAGE_WIDTH / 8) + x / 8;
+                let offset = 0x100 + tile * 16 + (y % 8) * 2;
+                if offset + 1 >= ram.len() {
+                    continue;
+                }
+                let bit = 0x80 >> (x % 8);
+                ram[offset] &= !bit;
+                ram[offset + 1] &= !bit;
+                if (color & 0x01) == 0x01 {
+                    ram[offset] |= bit;
+                }
+                if (color & 0x02) == 0x02 {
+                    ram[offset + 1] |= bit;
+                }
+            }
+        }
+    }
+}
+
+impl Default for Camera {
+    fn default() -> Camera {
+        return Camera::new();
+    }
+}
+
+impl Mbc for Camera {
+    fn read_rom(&self, rom: &[u8], addr: u16) -> u8 {
+        if addr < 0x4000 {
+            return super::rom_byte(rom, 0, addr);
+        }
+        return super::rom_byte(rom, self.rom_bank as usize, addr);
+    }
+
+    fn write_rom(&mut self, addr: u16, value: u8) {
+        if addr < 0x2000 {
+            self.ram_enabled = (value & 0x0f) == 0x0a;
+        } else if addr < 0x4000 {
+            self.rom_bank = value & 0x3f;
+        } else if addr < 0x6000 {
+            self.ram_bank = value & 0x1f;
+        }
+    }
+
+    fn read_ram(&self, ram: &[u8], addr: u16) -> u8 {
+        if (self.ram_bank & 0x10) == 0x10 {
+            // Only the busy flag of the sensor registers can be read back.
+            if (addr & 0x7f) == 0x00 {
+                return self.registers[0] & 0x07;
+            }
+            return 0x00;
+        }
+        // RAM can not be read while a capture is in progress.
+        if self.capture_clocks > 0 {
+            return 0x00;
+        }
+        return match super::ram_offset(ram, self.ram_bank as usize, addr) {
+            Some(offset) => ram[offset],
+            None => 0xff,
+        };
+    }
+
+    fn write_ram(&mut self, ram: &mut [u8], addr: u16, value: u8) {
+        if (self.ram_bank & 0x10) == 0x10 {
+            let register = (addr & 0x7f) as usize;
+            if register < self.registers.len() {
+                self.registers[register] = value;
+            }
+            if register == 0x00 && (value & 0x01) == 0x01 {
+                self.capture(ram);
+                self.capture_clocks = CAPTURE_CLOCKS;
+            }
+            return;
+        }
+        if !self.ram_enabled {
+            return;
+        }
+        if let Some(offset) = super::ram_offset(ram, self.ram_bank as usize, addr) {
+            ram[offset] = value;
+        }
+    }
+
+    fn tick(&mut self, clocks: u32) {
+        if self.capture_clocks == 0 {
+            return;
+        }
+        self.capture_clocks = self.capture_clocks.saturating_sub(clocks);
+        if self.capture_clocks == 0 {
+            self.registers[0] &= !0x01;
+        }
+    }
+
+    fn set_camera_image(&mut self, image: Vec<u8>) {
+        if image.len() == self.image.len() {
+            self.image = image;
+        }
+    }
+}
+
+// Load a binary (P5) or plain (P2) PGM image as the sensor image, scaled to the sensor size.
+pub fn load_image(filename: &str) -> Result<Vec<u8>, String> {
+    let data = std::fs::read(filename).map_err(|e| format!("{}: {}", filename, e))?;
+    let error = || format!("{}: not a valid PGM image", filename);
+
+    // The header is four whitespace separated fields, comments start with '#'.
+    let mut fields = Vec::new();
+    let mut position = 0;
+    while fields.len() < 4 {
+        while position < data.len() && data[position].is_ascii_whitespace() {
+            position += 1;
+        }
+        if position < data.len() && data[position] == b'#' {
+            while position < data.len() && data[position] != b'\n' {
+                position += 1;
+            }
+            continue;
+        }
+        let start = position;
+        while position < data.len() && !data[position].is_ascii_whitespace() {
+            position += 1;
+        }
+        if start == position {
+            return Err(error());
+        }
+        fields.push(String::from_utf8_lossy(&data[start..position]).to_string());
+    }
+    let width: usize = fields[1].parse().map_err(|_| error())?;
+    let height: usize = fields[2].parse().map_err(|_| error())?;
+    let max_value: usize = fields[3].parse().map_err(|_| error())?;
+    if width == 0 || height == 0 || max_value == 0 || max_value > 255 {
+        return Err(error());
+    }
+
+    let pixels: Vec<usize> = match fields[0].as_str() {
+        "P5" => data
+            .get(position + 1..)
+            .unwrap_or(&[])
+            .iter()
+            .map(|v| *v as usize)
+            .collect(),
+        "P2" => String::from_utf8_lossy(&data[position..])
+            .split_ascii_whitespace()
+            .map(|v| v.parse().unwrap_or(0))
+            .collect(),
+        _ => return Err(error()),
+    };
+    if pixels.len() < width * height {
+        return Err(error());
+    }
+
+    let mut image = vec![0; IMAGE_WIDTH * IMAGE_HEIGHT];
+    for y in 0..IMAGE_HEIGHT {
+        for x in 0..IMAGE_WIDTH {
+            let value = pixels[(y * height / IMAGE_HEIGHT) * width + x * width / IMAGE_WIDTH];
+            image[y * IMAGE_WIDTH + x] = (value.min(max_value) * 255 / max_value) as u8;
+        }
+    }
+    return Ok(image);
+}
@@ -0,0 +1,64 @@
+use super::Mbc;
+
+// HuC1 is an MBC1 like mapper where the RAM enable register instead selects between the
+// cartridge RAM and an infrared transceiver at 0xa000-0xbfff.
+pub struct HuC1 {
+    ir_mode: bool,
+    rom_bank: u8,
+    ram_bank: u8,
+}
+
+impl HuC1 {
+    pub fn new() -> HuC1 {
+        return HuC1 {
+            ir_mode: false,
+            rom_bank: 1,
+            ram_bank: 0,
+        };
+    }
+}
+
+impl Default for HuC1 {
+    fn default() -> HuC1 {
+        return HuC1::new();
+    }
+}
+
+impl Mbc for HuC1 {
+    fn read_rom(&self, rom: &[u8], addr: u16) -> u8 {
+        if addr < 0x4000 {
+            return super::rom_byte(rom, 0, addr);
+        }
+        return super::rom_byte(rom, self.rom_bank as usize, addr);
+    }
+
+    fn write_rom(&mut self, addr: u16, value: u8) {
+        if addr < 0x2000 {
+            self.ir_mode = value == 0x0e;
+        } else if addr < 0x4000 {
+            self.rom_bank = value & 0x3f;
+        } else if addr < 0x6000 {
+            self.ram_bank = value & 0x03;
+        }
+    }
+
+    fn read_ram(&self, ram: &[u8], addr: u16) -> u8 {
+        if self.ir_mode {
+            // No other infrared device is ever seen, so report no light.
+            return 0xc0;
+        }
+        return match super::ram_offset(ram, self.ram_bank as usize, addr) {
+            Some(offset) => ram[offset],
+            None => 0xff,
+        };
+    }
+
+    fn write_ram(&mut self, ram: &mut [u8], addr: u16, value: u8) {
+        if self.ir_mode {
+            return;
+        }
+        if let Some(offset) = super::ram_offset(ram, self.ram_bank as usize, addr) {
+            ram[offset] = value;
+        }
+    }
+}
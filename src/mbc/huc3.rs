@@ -0,0 +1,177 @@
+use super::rtc::{self, RtcMode};
+use super::Mbc;
+
+const CLOCKS_PER_MINUTE: u32 = 4194304 * 60;
+
+// HuC3 adds a clock that counts minutes of the day and days, accessed through a small
+// nibble wide command interface selected with the RAM enable register.
+pub struct HuC3 {
+    mode: u8,
+    rom_bank: u8,
+    ram_bank: u8,
+
+    // Scratch memory the clock is copied to and from, the minutes are stored at 0x00-0x02
+    // and the days at 0x03-0x05, one nibble per address.
+    memory: [u8; 0x100],
+    address: u8,
+    result: u8,
+
+    minutes: u16,
+    days: u16,
+    rtc_mode: RtcMode,
+    clocks: u32,
+    host_time: u64,
+}
+
+impl HuC3 {
+    pub fn new(rtc_mode: RtcMode) -> HuC3 {
+        return HuC3 {
+            mode: 0,
+            rom_bank: 1,
+            ram_bank: 0,
+
+            memory: [0; 0x100],
+            address: 0,
+            result: 0,
+
+            minutes: 0,
+            days: 0,
+            rtc_mode: rtc_mode,
+            clocks: 0,
+            host_time: rtc::host_seconds(),
+        };
+    }
+
+    fn execute(&mut self, value: u8) {
+        let argument = value & 0x0f;
+        match (value >> 4) & 0x07 {
+            0x1 => {
+                self.result = self.memory[self.address as usize] & 0x0f;
+                self.address = self.address.wrapping_add(1);
+            }
+            0x3 => {
+                self.memory[self.address as usize] = argument;
+                self.address = self.address.wrapping_add(1);
+            }
+            0x4 => self.address = (self.address & 0xf0) | argument,
+            0x5 => self.address = (self.address & 0x0f) | (argument << 4),
+            0x6 => match argument {
+                0x0 => {
+                    self.sync_host();
+                    for n in 0..3 {
+                        self.memory[n] = ((self.minutes >> (n * 4)) & 0x0f) as u8;
+                        self.memory[n + 3] = ((self.days >> (n * 4)) & 0x0f) as u8;
+                    }
+                }
+                0x1 => {
+                    self.sync_host();
+                    self.minutes = 0;
+                    self.days = 0;
+                    for n in 0..3 {
+                        self.minutes |= (self.memory[n] as u16 & 0x0f) << (n * 4);
+                        self.days |= (self.memory[n + 3] as u16 & 0x0f) << (n * 4);
+                    }
+                    self.minutes %= 24 * 60;
+                }
+                // Status request, always report ready.
+                0x2 => self.result = 0x01,
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+
+    fn advance_minutes(&mut self, minutes: u64) {
+        let total = self.minutes as u64 + minutes;
+        self.minutes = (total % (24 * 60)) as u16;
+        self.days = self.days.wrapping_add((total / (24 * 60)) as u16);
+    }
+
+    fn sync_host(&mut self) {
+        if self.rtc_mode != RtcMode::Host {
+            return;
+        }
+        let minutes = rtc::host_periods_elapsed(&mut self.host_time, 60);
+        self.advance_minutes(minutes);
+    }
+}
+
+impl Mbc for HuC3 {
+    fn read_rom(&self, rom: &[u8], addr: u16) -> u8 {
+        if addr < 0x4000 {
+            return super::rom_byte(rom, 0, addr);
+        }
+        return super::rom_byte(rom, self.rom_bank as usize, addr);
+    }
+
+    fn write_rom(&mut self, addr: u16, value: u8) {
+        if addr < 0x2000 {
+            self.mode = value & 0x0f;
+        } else if addr < 0x4000 {
+            self.rom_bank = value & 0x7f;
+        } else if addr < 0x6000 {
+            self.ram_bank = value & 0x03;
+        }
+    }
+
+    fn read_ram(&self, ram: &[u8], addr: u16) -> u8 {
+        return match self.mode {
+            0x00 | 0x0a => match super::ram_offset(ram, self.ram_bank as usize, addr) {
+                Some(offset) => ram[offset],
+                None => 0xff,
+            },
+            0x0c => 0x80 | self.result,
+            // The semaphore reads as ready, and the infrared receiver never sees light.
+            0x0d => 0x01,
+            0x0e => 0xc0,
+            _ => 0xff,
+        };
+    }
+
+    fn write_ram(&mut self, ram: &mut [u8], addr: u16, value: u8) {
+        match self.mode {
+            0x0a => {
+                if let Some(offset) = super::ram_offset(ram, self.ram_bank as usize, addr) {
+                    ram[offset] = value;
+                }
+            }
+            0x0b => self.execute(value),
+            _ => {}
+        }
+    }
+
+    fn tick(&mut self, clocks: u32) {
+        if self.rtc_mode != RtcMode::Emulated {
+            return;
+        }
+        self.clocks += clocks;
+        if self.clocks >= CLOCKS_PER_MINUTE {
+            self.clocks -= CLOCKS_PER_MINUTE;
+            self.advance_minutes(1);
+        }
+    }
+
+    // Stored after the RAM in the save file as the minutes and days as 32 bit little endian
+    // values, followed by a 64 bit unix timestamp.
+    fn save_rtc(&mut self) -> Vec<u8> {
+        self.sync_host();
+        let mut data = Vec::new();
+        data.extend_from_slice(&(self.minutes as u32).to_le_bytes());
+        data.extend_from_slice(&(self.days as u32).to_le_bytes());
+        data.extend_from_slice(&self.host_time.to_le_bytes());
+        return data;
+    }
+
+    fn load_rtc(&mut self, data: &[u8]) {
+        if data.len() < 16 {
+            return;
+        }
+        let value = |n: usize| u32::from_le_bytes([data[n], data[n + 1], data[n + 2], data[n + 3]]);
+        self.minutes = (value(0) % (24 * 60)) as u16;
+        self.days = value(4) as u16;
+        let mut timestamp = [0; 8];
+        timestamp.copy_from_slice(&data[8..16]);
+        self.host_time = u64::from_le_bytes(timestamp);
+        self.sync_host();
+    }
+}
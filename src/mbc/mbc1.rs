@@ -0,0 +1,92 @@
+use super::Mbc;
+
+pub struct Mbc1 {
+    ram_enabled: bool,
+    bank_low: u8,
+    bank_high: u8,
+    advanced_banking: bool,
+    // MBC1M multicarts wire only 4 bits of the low bank register to the ROM.
+    multicart: bool,
+}
+
+impl Mbc1 {
+    pub fn new(multicart: bool) -> Mbc1 {
+        return Mbc1 {
+            ram_enabled: false,
+            bank_low: 1,
+            bank_high: 0,
+            advanced_banking: false,
+            multicart: multicart,
+        };
+    }
+
+    fn high_bank(&self) -> usize {
+        let shift = if self.multicart { 4 } else { 5 };
+        return (self.bank_high as usize) << shift;
+    }
+}
+
+impl Mbc for Mbc1 {
+    fn read_rom(&self, rom: &[u8], addr: u16) -> u8 {
+        if addr < 0x4000 {
+            // In advanced banking mode the upper bits also apply to the 0x0000-0x3fff area.
+            let bank = if self.advanced_banking {
+                self.high_bank()
+            } else {
+                0
+            };
+            return super::rom_byte(rom, bank, addr);
+        }
+        let low = if self.multicart {
+            self.bank_low & 0x0f
+        } else {
+            self.bank_low
+        };
+        return super::rom_byte(rom, self.high_bank() | low as usize, addr);
+    }
+
+    fn write_rom(&mut self, addr: u16, value: u8) {
+        if addr < 0x2000 {
+            self.ram_enabled = (value & 0x0f) == 0x0a;
+        } else if addr < 0x4000 {
+            // Bank 0 can not be selected, the register check is done on all 5 bits.
+            self.bank_low = value & 0x1f;
+            if self.bank_low == 0 {
+                self.bank_low = 1;
+            }
+        } else if addr < 0x6000 {
+            self.bank_high = value & 0x03;
+        } else {
+            self.advanced_banking = (value & 0x01) == 0x01;
+        }
+    }
+
+    fn read_ram(&self, ram: &[u8], addr: u16) -> u8 {
+        if !self.ram_enabled {
+            return 0xff;
+        }
+        let bank = if self.advanced_banking {
+            self.bank_high as usize
+        } else {
+            0
+        };
+        return match super::ram_offset(ram, bank, addr) {
+            Some(offset) => ram[offset],
+            None => 0xff,
+        };
+    }
+
+    fn write_ram(&mut self, ram: &mut [u8], addr: u16, value: u8) {
+        if !self.ram_enabled {
+            return;
+        }
+        let bank = if self.advanced_banking {
+            self.bank_high as usize
+        } else {
+            0
+        };
+        if let Some(offset) = super::ram_offset(ram, bank, addr) {
+            ram[offset] = value;
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use super::Mbc;
+
+// MBC2 has 512 half-bytes of RAM built in, echoed across the whole 0xa000-0xbfff area.
+pub struct Mbc2 {
+    ram_enabled: bool,
+    rom_bank: u8,
+}
+
+impl Mbc2 {
+    pub const RAM_SIZE: usize = 0x200;
+
+    pub fn new() -> Mbc2 {
+        return Mbc2 {
+            ram_enabled: false,
+            rom_bank: 1,
+        };
+    }
+}
+
+impl Default for Mbc2 {
+    fn default() -> Mbc2 {
+        return Mbc2::new();
+    }
+}
+
+impl Mbc for Mbc2 {
+    fn read_rom(&self, rom: &[u8], addr: u16) -> u8 {
+        if addr < 0x4000 {
+            return super::rom_byte(rom, 0, addr);
+        }
+        return super::rom_byte(rom, self.rom_bank as usize, addr);
+    }
+
+    fn write_rom(&mut self, addr: u16, value: u8) {
+        if addr >= 0x4000 {
+            return;
+        }
+        // Address bit 8 selects between the RAM enable and ROM bank registers.
+        if (addr & 0x0100) == 0x0000 {
+            self.ram_enabled = (value & 0x0f) == 0x0a;
+        } else {
+            self.rom_bank = value & 0x0f;
+            if self.rom_bank == 0 {
+                self.rom_bank = 1;
+            }
+        }
+    }
+
+    fn read_ram(&self, ram: &[u8], addr: u16) -> u8 {
+        if !self.ram_enabled || ram.is_empty() {
+            return 0xff;
+        }
+        return ram[addr as usize & 0x1ff] | 0xf0;
+    }
+
+    fn write_ram(&mut self, ram: &mut [u8], addr: u16, value: u8) {
+        if !self.ram_enabled || ram.is_empty() {
+            return;
+        }
+        ram[addr as usize & 0x1ff] = value & 0x0f;
+    }
+}
@@ -0,0 +1,102 @@
+use super::rtc::{Rtc, RtcMode};
+use super::Mbc;
+
+pub struct Mbc3 {
+    ram_enabled: bool,
+    rom_bank: u8,
+    // 0x00-0x07 selects a RAM bank, 0x08-0x0c an RTC register.
+    ram_bank: u8,
+    rtc: Option<Rtc>,
+}
+
+impl Mbc3 {
+    pub fn new(has_rtc: bool, rtc_mode: RtcMode) -> Mbc3 {
+        return Mbc3 {
+            ram_enabled: false,
+            rom_bank: 1,
+            ram_bank: 0,
+            rtc: if has_rtc {
+                Some(Rtc::new(rtc_mode))
+            } else {
+                None
+            },
+        };
+    }
+}
+
+impl Mbc for Mbc3 {
+    fn read_rom(&self, rom: &[u8], addr: u16) -> u8 {
+        if addr < 0x4000 {
+            return super::rom_byte(rom, 0, addr);
+        }
+        return super::rom_byte(rom, self.rom_bank as usize, addr);
+    }
+
+    fn write_rom(&mut self, addr: u16, value: u8) {
+        if addr < 0x2000 {
+            self.ram_enabled = (value & 0x0f) == 0x0a;
+        } else if addr < 0x4000 {
+            // 7 bits on MBC3, the 8th bit is used by the 4 MiB MBC30 variant.
+            self.rom_bank = value;
+            if self.rom_bank == 0 {
+                self.rom_bank = 1;
+            }
+        } else if addr < 0x6000 {
+            self.ram_bank = value & 0x0f;
+        } else if let Some(rtc) = &mut self.rtc {
+            rtc.write_latch(value);
+        }
+    }
+
+    fn read_ram(&self, ram: &[u8], addr: u16) -> u8 {
+        if !self.ram_enabled {
+            return 0xff;
+        }
+        if self.ram_bank >= 0x08 {
+            return match &self.rtc {
+                Some(rtc) if self.ram_bank <= 0x0c => rtc.read(self.ram_bank),
+                _ => 0xff,
+            };
+        }
+        return match super::ram_offset(ram, self.ram_bank as usize, addr) {
+            Some(offset) => ram[offset],
+            None => 0xff,
+        };
+    }
+
+    fn write_ram(&mut self, ram: &mut [u8], addr: u16, value: u8) {
+        if !self.ram_enabled {
+            return;
+        }
+        if self.ram_bank >= 0x08 {
+            if let Some(rtc) = &mut self.rtc {
+                if self.ram_bank <= 0x0c {
+                    rtc.write(self.ram_bank, value);
+                }
+            }
+            return;
+        }
+        if let Some(offset) = super::ram_offset(ram, self.ram_bank as usize, addr) {
+            ram[offset] = value;
+        }
+    }
+
+    fn save_rtc(&mut self) -> Vec<u8> {
+        return match &mut self.rtc {
+            Some(rtc) => rtc.save(),
+            None => Vec::new(),
+        };
+    }
+
+    fn load_rtc(&mut self, data: &[u8]) {
+        if let Some(rtc) = &mut self.rtc {
+            rtc.load(data);
+        }
+    }
+
+    fn tick(&mut self, clocks: u32) {
+        if let Some(rtc) = &mut self.rtc {
+            rtc.tick(clocks);
+        }
+    }
+}
@@ -0,0 +1,72 @@
+use super::Mbc;
+
+pub struct Mbc5 {
+    ram_enabled: bool,
+    rom_bank: u16,
+    ram_bank: u8,
+    // Rumble carts use bit 3 of the RAM bank register to drive the motor.
+    has_rumble: bool,
+    rumble: bool,
+}
+
+impl Mbc5 {
+    pub fn new(has_rumble: bool) -> Mbc5 {
+        return Mbc5 {
+            ram_enabled: false,
+            rom_bank: 1,
+            ram_bank: 0,
+            has_rumble: has_rumble,
+            rumble: false,
+        };
+    }
+}
+
+impl Mbc for Mbc5 {
+    fn read_rom(&self, rom: &[u8], addr: u16) -> u8 {
+        if addr < 0x4000 {
+            return super::rom_byte(rom, 0, addr);
+        }
+        // Unlike the older controllers bank 0 can be mapped here.
+        return super::rom_byte(rom, self.rom_bank as usize, addr);
+    }
+
+    fn write_rom(&mut self, addr: u16, value: u8) {
+        if addr < 0x2000 {
+            self.ram_enabled = value == 0x0a;
+        } else if addr < 0x3000 {
+            self.rom_bank = (self.rom_bank & 0x100) | value as u16;
+        } else if addr < 0x4000 {
+            self.rom_bank = (self.rom_bank & 0xff) | ((value as u16 & 0x01) << 8);
+        } else if addr < 0x6000 {
+            if self.has_rumble {
+                self.rumble = (value & 0x08) == 0x08;
+                self.ram_bank = value & 0x07;
+            } else {
+                self.ram_bank = value & 0x0f;
+            }
+        }
+    }
+
+    fn read_ram(&self, ram: &[u8], addr: u16) -> u8 {
+        if !self.ram_enabled {
+            return 0xff;
+        }
+        return match super::ram_offset(ram, self.ram_bank as usize, addr) {
+            Some(offset) => ram[offset],
+            None => 0xff,
+        };
+    }
+
+    fn write_ram(&mut self, ram: &mut [u8], addr: u16, value: u8) {
+        if !self.ram_enabled {
+            return;
+        }
+        if let Some(offset) = super::ram_offset(ram, self.ram_bank as usize, addr) {
+            ram[offset] = value;
+        }
+    }
+
+    fn rumble(&self) -> bool {
+        return self.rumble;
+    }
+}
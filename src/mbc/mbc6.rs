@@ -0,0 +1,162 @@
+use super::Mbc;
+
+const FLASH_SIZE: usize = 0x100000;
+const FLASH_SECTOR_SIZE: usize = 0x20000;
+
+// Progress through the command sequences of the flash chip, which all start with the
+// unlock writes of 0xaa to 0x5555 and 0x55 to 0x2aaa.
+#[derive(Clone, Copy, PartialEq)]
+enum FlashState {
+    Read,
+    Unlock1,
+    Unlock2,
+    Program,
+    Erase,
+    EraseUnlock1,
+    EraseUnlock2,
+}
+
+// MBC6 splits both the ROM area and the RAM area into two independently switched halves,
+// 8 KiB ROM banks at 0x4000/0x6000 and 4 KiB RAM banks at 0xa000/0xb000. The ROM halves can
+// map the 1 MiB flash chip instead, which is programmed and erased through its command
+// protocol while write enabled. The flash contents are saved after the RAM.
+pub struct Mbc6 {
+    ram_enabled: bool,
+    ram_bank: [u8; 2],
+    flash_enabled: bool,
+    flash_write_enabled: bool,
+    rom_bank: [u8; 2],
+    flash_selected: [bool; 2],
+    flash: Vec<u8>,
+    flash_state: FlashState,
+}
+
+impl Mbc6 {
+    pub fn new() -> Mbc6 {
+        return Mbc6 {
+            ram_enabled: false,
+            ram_bank: [0, 0],
+            flash_enabled: false,
+            flash_write_enabled: false,
+            rom_bank: [0, 0],
+            flash_selected: [false, false],
+            flash: vec![0xff; FLASH_SIZE],
+            flash_state: FlashState::Read,
+        };
+    }
+
+    fn ram_offset(&self, ram: &[u8], addr: u16) -> Option<usize> {
+        if ram.is_empty() {
+            return None;
+        }
+        let half = ((addr >> 12) & 0x01) as usize;
+        return Some(
+            (self.ram_bank[half] as usize * 0x1000 + (addr as usize & 0x0fff)) % ram.len(),
+        );
+    }
+
+    fn write_flash(&mut self, offset: usize, value: u8) {
+        // Commands only decode the low 15 address bits, 0xf0 aborts any sequence.
+        let command_addr = offset & 0x7fff;
+        if value == 0xf0 && self.flash_state != FlashState::Program {
+            self.flash_state = FlashState::Read;
+            return;
+        }
+        self.flash_state = match (self.flash_state, command_addr, value) {
+            (FlashState::Read, 0x5555, 0xaa) => FlashState::Unlock1,
+            (FlashState::Unlock1, 0x2aaa, 0x55) => FlashState::Unlock2,
+            (FlashState::Unlock2, 0x5555, 0xa0) => FlashState::Program,
+            (FlashState::Unlock2, 0x5555, 0x80) => FlashState::Erase,
+            (FlashState::Program, _, _) => {
+                // Programming can only clear bits, erasing sets them again.
+                self.flash[offset] &= value;
+                FlashState::Read
+            }
+            (FlashState::Erase, 0x5555, 0xaa) => FlashState::EraseUnlock1,
+            (FlashState::EraseUnlock1, 0x2aaa, 0x55) => FlashState::EraseUnlock2,
+            (FlashState::EraseUnlock2, 0x5555, 0x10) => {
+                self.flash.fill(0xff);
+                FlashState::Read
+            }
+            (FlashState::EraseUnlock2, _, 0x30) => {
+                let sector = offset - offset % FLASH_SECTOR_SIZE;
+                self.flash[sector..sector + FLASH_SECTOR_SIZE].fill(0xff);
+                FlashState::Read
+            }
+            _ => FlashState::Read,
+        };
+    }
+}
+
+impl Default for Mbc6 {
+    fn default() -> Mbc6 {
+        return Mbc6::new();
+    }
+}
+
+impl Mbc for Mbc6 {
+    fn read_rom(&self, rom: &[u8], addr: u16) -> u8 {
+        if addr < 0x4000 {
+            return super::rom_byte(rom, 0, addr);
+        }
+        let half = ((addr >> 13) & 0x01) as usize;
+        let offset = self.rom_bank[half] as usize * 0x2000 + (addr as usize & 0x1fff);
+        if self.flash_selected[half] {
+            if !self.flash_enabled {
+                return 0xff;
+            }
+            return self.flash[offset % FLASH_SIZE];
+        }
+        return rom.get(offset % rom.len()).copied().unwrap_or(0xff);
+    }
+
+    fn write_rom(&mut self, addr: u16, value: u8) {
+        match addr {
+            0x0000..=0x03ff => self.ram_enabled = (value & 0x0f) == 0x0a,
+            0x0400..=0x07ff => self.ram_bank[0] = value & 0x07,
+            0x0800..=0x0bff => self.ram_bank[1] = value & 0x07,
+            0x0c00..=0x0fff => self.flash_enabled = (value & 0x01) == 0x01,
+            0x1000 => self.flash_write_enabled = (value & 0x01) == 0x01,
+            0x2000..=0x27ff => self.rom_bank[0] = value & 0x7f,
+            0x2800..=0x2fff => self.flash_selected[0] = value == 0x08,
+            0x3000..=0x37ff => self.rom_bank[1] = value & 0x7f,
+            0x3800..=0x3fff => self.flash_selected[1] = value == 0x08,
+            0x4000..=0x7fff => {
+                let half = ((addr >> 13) & 0x01) as usize;
+                if self.flash_selected[half] && self.flash_enabled && self.flash_write_enabled {
+                    let offset = self.rom_bank[half] as usize * 0x2000 + (addr as usize & 0x1fff);
+                    self.write_flash(offset % FLASH_SIZE, value);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn read_ram(&self, ram: &[u8], addr: u16) -> u8 {
+        if !self.ram_enabled {
+            return 0xff;
+        }
+        return match self.ram_offset(ram, addr) {
+            Some(offset) => ram[offset],
+            None => 0xff,
+        };
+    }
+
+    fn write_ram(&mut self, ram: &mut [u8], addr: u16, value: u8) {
+        if !self.ram_enabled {
+            return;
+        }
+        if let Some(offset) = self.ram_offset(ram, addr) {
+            ram[offset] = value;
+        }
+    }
+
+    fn save_rtc(&mut self) -> Vec<u8> {
+        return self.flash.clone();
+    }
+
+    fn load_rtc(&mut self, data: &[u8]) {
+        let length = FLASH_SIZE.min(data.len());
+        self.flash[..length].copy_from_slice(&data[..length]);
+    }
+}
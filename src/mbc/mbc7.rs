@@ -0,0 +1,269 @@
+use super::Mbc;
+
+// MBC7 has no RAM, instead 0xa000-0xafff holds the accelerometer registers and the serial
+// interface to a 93LC56 EEPROM. The EEPROM contents are stored in the cartridge RAM buffer
+// as 128 little endian 16 bit words, so they are persisted like normal battery backed RAM.
+pub struct Mbc7 {
+    ram_enabled: [bool; 2],
+    rom_bank: u8,
+
+    tilt_x: f32,
+    tilt_y: f32,
+    latch_x: u16,
+    latch_y: u16,
+    latch_armed: bool,
+
+    eeprom: Eeprom,
+}
+
+#[derive(PartialEq)]
+enum EepromState {
+    Idle,
+    Command,
+    Read,
+    Write,
+    WriteAll,
+}
+
+struct Eeprom {
+    chip_select: bool,
+    clock: bool,
+    data_in: bool,
+    data_out: bool,
+    write_enabled: bool,
+
+    state: EepromState,
+    shift: u16,
+    bits: u8,
+    address: usize,
+}
+
+impl Mbc7 {
+    pub const RAM_SIZE: usize = 0x100;
+
+    pub fn new() -> Mbc7 {
+        return Mbc7 {
+            ram_enabled: [false, false],
+            rom_bank: 1,
+
+            tilt_x: 0.0,
+            tilt_y: 0.0,
+            latch_x: 0x8000,
+            latch_y: 0x8000,
+            latch_armed: false,
+
+            eeprom: Eeprom {
+                chip_select: false,
+                clock: false,
+                data_in: false,
+                data_out: true,
+                write_enabled: false,
+
+                state: EepromState::Idle,
+                shift: 0,
+                bits: 0,
+                address: 0,
+            },
+        };
+    }
+}
+
+impl Default for Mbc7 {
+    fn default() -> Mbc7 {
+        return Mbc7::new();
+    }
+}
+
+impl Eeprom {
+    fn read(&self) -> u8 {
+        let mut result = 0x00;
+        if self.chip_select {
+            result |= 0x80;
+        }
+        if self.clock {
+            result |= 0x40;
+        }
+        if self.data_in {
+            result |= 0x02;
+        }
+        if self.data_out {
+            result |= 0x01;
+        }
+        return result;
+    }
+
+    fn write(&mut self, ram: &mut [u8], value: u8) {
+        let chip_select = (value & 0x80) == 0x80;
+        let clock = (value & 0x40) == 0x40;
+        self.data_in = (value & 0x02) == 0x02;
+        if chip_select && !self.chip_select {
+            self.state = EepromState::Idle;
+            self.data_out = true;
+        }
+        if chip_select && clock && !self.clock {
+            self.clock_rising(ram);
+        }
+        self.chip_select = chip_select;
+        self.clock = clock;
+    }
+
+    fn clock_rising(&mut self, ram: &mut [u8]) {
+        let bit = if self.data_in { 1 } else { 0 };
+        match self.state {
+            EepromState::Idle => {
+                if bit == 1 {
+                    self.state = EepromState::Command;
+                    self.shift = 0;
+                    self.bits = 0;
+                }
+            }
+            EepromState::Command => {
+                self.shift = (self.shift << 1) | bit;
+                self.bits += 1;
+                if self.bits == 10 {
+                    self.command(ram);
+                }
+            }
+            EepromState::Read => {
+                self.data_out = (self.shift & 0x8000) == 0x8000;
+                self.shift <<= 1;
+                self.bits += 1;
+                if self.bits == 16 {
+                    self.state = EepromState::Idle;
+                }
+            }
+            EepromState::Write | EepromState::WriteAll => {
+                self.shift = (self.shift << 1) | bit;
+                self.bits += 1;
+                if self.bits == 16 {
+                    if self.write_enabled {
+                        if self.state == EepromState::WriteAll {
+                            for address in 0..ram.len() / 2 {
+                                Eeprom::store(ram, address, self.shift);
+                            }
+                        } else {
+                            Eeprom::store(ram, self.address, self.shift);
+                        }
+                    }
+                    self.data_out = true;
+                    self.state = EepromState::Idle;
+                }
+            }
+        }
+    }
+
+    // Commands are a 2 bit opcode followed by an 8 bit address, of which 7 bits are used.
+    fn command(&mut self, ram: &mut [u8]) {
+        self.address = (self.shift & 0x7f) as usize;
+        self.bits = 0;
+        self.state = EepromState::Idle;
+        match (self.shift >> 8) & 0x03 {
+            0b10 => {
+                self.shift = Eeprom::load(ram, self.address);
+                self.data_out = false;
+                self.state = EepromState::Read;
+            }
+            0b01 => {
+                self.shift = 0;
+                self.state = EepromState::Write;
+            }
+            0b11 => {
+                if self.write_enabled {
+                    Eeprom::store(ram, self.address, 0xffff);
+                }
+                self.data_out = true;
+            }
+            _ => match (self.shift >> 6) & 0x03 {
+                0b11 => self.write_enabled = true,
+                0b00 => self.write_enabled = false,
+                0b10 => {
+                    if self.write_enabled {
+                        for address in 0..ram.len() / 2 {
+                            Eeprom::store(ram, address, 0xffff);
+                        }
+                    }
+                    self.data_out = true;
+                }
+                _ => {
+                    self.shift = 0;
+                    self.state = EepromState::WriteAll;
+                }
+            },
+        }
+    }
+
+    fn load(ram: &[u8], address: usize) -> u16 {
+        if ram.len() < address * 2 + 2 {
+            return 0xffff;
+        }
+        return ram[address * 2] as u16 | (ram[address * 2 + 1] as u16) << 8;
+    }
+
+    fn store(ram: &mut [u8], address: usize, value: u16) {
+        if ram.len() < address * 2 + 2 {
+            return;
+        }
+        ram[address * 2] = (value & 0xff) as u8;
+        ram[address * 2 + 1] = (value >> 8) as u8;
+    }
+}
+
+impl Mbc for Mbc7 {
+    fn read_rom(&self, rom: &[u8], addr: u16) -> u8 {
+        if addr < 0x4000 {
+            return super::rom_byte(rom, 0, addr);
+        }
+        return super::rom_byte(rom, self.rom_bank as usize, addr);
+    }
+
+    fn write_rom(&mut self, addr: u16, value: u8) {
+        if addr < 0x2000 {
+            self.ram_enabled[0] = value == 0x0a;
+        } else if addr < 0x4000 {
+            self.rom_bank = value & 0x7f;
+        } else if addr < 0x6000 {
+            self.ram_enabled[1] = value == 0x40;
+        }
+    }
+
+    fn read_ram(&self, _ram: &[u8], addr: u16) -> u8 {
+        if !self.ram_enabled[0] || !self.ram_enabled[1] || addr >= 0xb000 {
+            return 0xff;
+        }
+        return match (addr >> 4) & 0x0f {
+            0x2 => (self.latch_x & 0xff) as u8,
+            0x3 => (self.latch_x >> 8) as u8,
+            0x4 => (self.latch_y & 0xff) as u8,
+            0x5 => (self.latch_y >> 8) as u8,
+            0x6 => 0x00,
+            0x8 => self.eeprom.read(),
+            _ => 0xff,
+        };
+    }
+
+    fn write_ram(&mut self, ram: &mut [u8], addr: u16, value: u8) {
+        if !self.ram_enabled[0] || !self.ram_enabled[1] || addr >= 0xb000 {
+            return;
+        }
+        match (addr >> 4) & 0x0f {
+            0x0 if value == 0x55 => {
+                self.latch_x = 0x8000;
+                self.latch_y = 0x8000;
+                self.latch_armed = true;
+            }
+            0x1 if value == 0xaa && self.latch_armed => {
+                // About 0x70 counts per g around a center value of 0x81d0.
+                self.latch_x = (0x81d0 as f32 + 0x70 as f32 * self.tilt_x) as u16;
+                self.latch_y = (0x81d0 as f32 + 0x70 as f32 * self.tilt_y) as u16;
+                self.latch_armed = false;
+            }
+            0x8 => self.eeprom.write(ram, value),
+            _ => {}
+        }
+    }
+
+    fn set_tilt(&mut self, x: f32, y: f32) {
+        self.tilt_x = x;
+        self.tilt_y = y;
+    }
+}
@@ -0,0 +1,108 @@
+use super::Mbc;
+
+// MMM01 multicart mapper. At power on the last 32 KiB of ROM (the menu) is mapped, the menu
+// then selects a game by writing the upper bank bits and locks them with bit 6 of the RAM
+// enable register. After that it behaves as an MBC1 within the selected part of the ROM.
+pub struct Mmm01 {
+    locked: bool,
+    ram_enabled: bool,
+    rom_bank_low: u8,
+    rom_bank_high: u8,
+    // ROM bank bits 1-4 that are fixed once locked.
+    rom_bank_mask: u8,
+    ram_bank_low: u8,
+    ram_bank_high: u8,
+}
+
+impl Mmm01 {
+    pub fn new() -> Mmm01 {
+        return Mmm01 {
+            locked: false,
+            ram_enabled: false,
+            rom_bank_low: 0,
+            rom_bank_high: 0,
+            rom_bank_mask: 0,
+            ram_bank_low: 0,
+            ram_bank_high: 0,
+        };
+    }
+
+    fn base_bank(&self) -> usize {
+        return (self.rom_bank_high as usize) << 5
+            | (self.rom_bank_low & self.rom_bank_mask) as usize;
+    }
+}
+
+impl Default for Mmm01 {
+    fn default() -> Mmm01 {
+        return Mmm01::new();
+    }
+}
+
+impl Mbc for Mmm01 {
+    fn read_rom(&self, rom: &[u8], addr: u16) -> u8 {
+        if !self.locked {
+            let banks = rom.len() / 0x4000;
+            let bank = if addr < 0x4000 {
+                banks.saturating_sub(2)
+            } else {
+                banks.saturating_sub(1)
+            };
+            return super::rom_byte(rom, bank, addr);
+        }
+        if addr < 0x4000 {
+            return super::rom_byte(rom, self.base_bank(), addr);
+        }
+        let mut low = self.rom_bank_low & !self.rom_bank_mask;
+        if low == 0 {
+            low = 1;
+        }
+        return super::rom_byte(rom, self.base_bank() | low as usize, addr);
+    }
+
+    fn write_rom(&mut self, addr: u16, value: u8) {
+        if addr < 0x2000 {
+            self.ram_enabled = (value & 0x0f) == 0x0a;
+            if (value & 0x40) == 0x40 {
+                self.locked = true;
+            }
+        } else if addr < 0x4000 {
+            if self.locked {
+                self.rom_bank_low =
+                    (self.rom_bank_low & self.rom_bank_mask) | (value & 0x1f & !self.rom_bank_mask);
+            } else {
+                self.rom_bank_low = value & 0x1f;
+                self.rom_bank_high = (self.rom_bank_high & 0x0c) | ((value >> 5) & 0x03);
+            }
+        } else if addr < 0x6000 {
+            self.ram_bank_low = value & 0x03;
+            if !self.locked {
+                self.ram_bank_high = (value >> 2) & 0x03;
+                self.rom_bank_high = (self.rom_bank_high & 0x03) | ((value >> 2) & 0x0c);
+            }
+        } else if !self.locked {
+            self.rom_bank_mask = (value >> 1) & 0x1e;
+        }
+    }
+
+    fn read_ram(&self, ram: &[u8], addr: u16) -> u8 {
+        if !self.ram_enabled {
+            return 0xff;
+        }
+        let bank = (self.ram_bank_high << 2 | self.ram_bank_low) as usize;
+        return match super::ram_offset(ram, bank, addr) {
+            Some(offset) => ram[offset],
+            None => 0xff,
+        };
+    }
+
+    fn write_ram(&mut self, ram: &mut [u8], addr: u16, value: u8) {
+        if !self.ram_enabled {
+            return;
+        }
+        let bank = (self.ram_bank_high << 2 | self.ram_bank_low) as usize;
+        if let Some(offset) = super::ram_offset(ram, bank, addr) {
+            ram[offset] = value;
+        }
+    }
+}
@@ -0,0 +1,74 @@
+mod camera;
+mod huc1;
+mod huc3;
+mod mbc1;
+mod mbc2;
+mod mbc3;
+mod mbc5;
+mod mbc6;
+mod mbc7;
+mod mmm01;
+mod rom_only;
+mod rtc;
+
+pub use camera::load_image as load_camera_image;
+pub use camera::Camera;
+pub use huc1::HuC1;
+pub use huc3::HuC3;
+pub use mbc1::Mbc1;
+pub use mbc2::Mbc2;
+pub use mbc3::Mbc3;
+pub use mbc5::Mbc5;
+pub use mbc6::Mbc6;
+pub use mbc7::Mbc7;
+pub use mmm01::Mmm01;
+pub use rom_only::RomOnly;
+pub use rtc::RtcMode;
+
+// A memory bank controller maps the 0x0000-0x7fff and 0xa000-0xbfff regions onto the
+// cartridge ROM and RAM, which are owned by the cartridge and passed in on every access.
+pub trait Mbc {
+    fn read_rom(&self, rom: &[u8], addr: u16) -> u8;
+    fn write_rom(&mut self, addr: u16, value: u8);
+    fn read_ram(&self, ram: &[u8], addr: u16) -> u8;
+    fn write_ram(&mut self, ram: &mut [u8], addr: u16, value: u8);
+
+    // Called with the number of elapsed clocks, at the 4.194304 MHz rate regardless of CPU speed.
+    fn tick(&mut self, _clocks: u32) {}
+
+    // State stored after the RAM contents in the save file, the clock for carts with an RTC
+    // and the flash chip for MBC6.
+    fn save_rtc(&mut self) -> Vec<u8> {
+        return Vec::new();
+    }
+
+    fn load_rtc(&mut self, _data: &[u8]) {}
+
+    // State of the rumble motor, for carts that have one.
+    fn rumble(&self) -> bool {
+        return false;
+    }
+
+    // Accelerometer input for MBC7, in g with positive values tilting right and down.
+    fn set_tilt(&mut self, _x: f32, _y: f32) {}
+
+    // Sensor image for the Pocket Camera, 128x112 pixels of 8 bit brightness.
+    fn set_camera_image(&mut self, _image: Vec<u8>) {}
+}
+
+// Read from a 16 KiB ROM bank, bank numbers beyond the ROM size wrap around like the
+// unconnected upper address lines on real hardware.
+pub fn rom_byte(rom: &[u8], bank: usize, addr: u16) -> u8 {
+    if rom.is_empty() {
+        return 0xff;
+    }
+    return rom[(bank * 0x4000 + (addr as usize & 0x3fff)) % rom.len()];
+}
+
+// Offset into cartridge RAM for an address in a 8 KiB RAM bank, or None without RAM.
+pub fn ram_offset(ram: &[u8], bank: usize, addr: u16) -> Option<usize> {
+    if ram.is_empty() {
+        return None;
+    }
+    return Some((bank * 0x2000 + (addr as usize & 0x1fff)) % ram.len());
+}
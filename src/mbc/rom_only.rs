@@ -0,0 +1,37 @@
+use super::Mbc;
+
+// Cartridges without a bank controller, optionally with up to 8 KiB of RAM.
+pub struct RomOnly {}
+
+impl RomOnly {
+    pub fn new() -> RomOnly {
+        return RomOnly {};
+    }
+}
+
+impl Default for RomOnly {
+    fn default() -> RomOnly {
+        return RomOnly::new();
+    }
+}
+
+impl Mbc for RomOnly {
+    fn read_rom(&self, rom: &[u8], addr: u16) -> u8 {
+        return rom.get(addr as usize).copied().unwrap_or(0xff);
+    }
+
+    fn write_rom(&mut self, _addr: u16, _value: u8) {}
+
+    fn read_ram(&self, ram: &[u8], addr: u16) -> u8 {
+        return match super::ram_offset(ram, 0, addr) {
+            Some(offset) => ram[offset],
+            None => 0xff,
+        };
+    }
+
+    fn write_ram(&mut self, ram: &mut [u8], addr: u16, value: u8) {
+        if let Some(offset) = super::ram_offset(ram, 0, addr) {
+            ram[offset] = value;
+        }
+    }
+}
@@ -0,0 +1,198 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const CLOCKS_PER_SECOND: u32 = 4194304;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum RtcMode {
+    // Advance with the emulated clock, so the RTC stops when emulation is paused or slowed down.
+    Emulated,
+    // Follow the host wall clock.
+    Host,
+}
+
+// The MBC3 real time clock, registers are selected as 0x08-0x0c through the RAM bank register.
+pub struct Rtc {
+    seconds: u8,
+    minutes: u8,
+    hours: u8,
+    days: u16,
+    halt: bool,
+    carry: bool,
+
+    latched: [u8; 5],
+    latch_value: u8,
+
+    mode: RtcMode,
+    clocks: u32,
+    host_time: u64,
+}
+
+impl Rtc {
+    pub fn new(mode: RtcMode) -> Rtc {
+        return Rtc {
+            seconds: 0,
+            minutes: 0,
+            hours: 0,
+            days: 0,
+            halt: false,
+            carry: false,
+
+            latched: [0; 5],
+            latch_value: 0xff,
+
+            mode: mode,
+            clocks: 0,
+            host_time: host_seconds(),
+        };
+    }
+
+    pub fn tick(&mut self, clocks: u32) {
+        if self.mode != RtcMode::Emulated || self.halt {
+            return;
+        }
+        self.clocks += clocks;
+        while self.clocks >= CLOCKS_PER_SECOND {
+            self.clocks -= CLOCKS_PER_SECOND;
+            self.advance(1);
+        }
+    }
+
+    // Writing 0x00 followed by 0x01 copies the running clock into the readable registers.
+    pub fn write_latch(&mut self, value: u8) {
+        if self.latch_value == 0x00 && value == 0x01 {
+            self.sync_host();
+            self.latched = [
+                self.seconds,
+                self.minutes,
+                self.hours,
+                self.days as u8,
+                self.day_high(),
+            ];
+        }
+        self.latch_value = value;
+    }
+
+    pub fn read(&self, register: u8) -> u8 {
+        return self.latched[(register - 0x08) as usize];
+    }
+
+    pub fn write(&mut self, register: u8, value: u8) {
+        self.sync_host();
+        match register {
+            0x08 => {
+                self.seconds = value & 0x3f;
+                self.clocks = 0;
+            }
+            0x09 => self.minutes = value & 0x3f,
+            0x0a => self.hours = value & 0x1f,
+            0x0b => self.days = (self.days & 0x100) | value as u16,
+            _ => {
+                self.days = (self.days & 0xff) | ((value as u16 & 0x01) << 8);
+                self.halt = (value & 0x40) == 0x40;
+                self.carry = (value & 0x80) == 0x80;
+            }
+        }
+        self.latched[(register - 0x08) as usize] = match register {
+            0x0c => self.day_high(),
+            _ => value,
+        };
+    }
+
+    // Serialize in the 48 byte format other emulators append to the save file: the running and
+    // latched registers as 32 bit little endian values followed by a 64 bit unix timestamp.
+    pub fn save(&mut self) -> Vec<u8> {
+        self.sync_host();
+        let registers = [
+            self.seconds,
+            self.minutes,
+            self.hours,
+            self.days as u8,
+            self.day_high(),
+        ];
+        let mut data = Vec::new();
+        for value in registers.iter().chain(self.latched.iter()) {
+            data.extend_from_slice(&(*value as u32).to_le_bytes());
+        }
+        data.extend_from_slice(&self.host_time.to_le_bytes());
+        return data;
+    }
+
+    // Accepts the 48 byte format and the older 44 byte one with a 32 bit timestamp.
+    pub fn load(&mut self, data: &[u8]) {
+        if data.len() < 44 {
+            return;
+        }
+        let register = |n: usize| data[n * 4];
+        self.seconds = register(0) & 0x3f;
+        self.minutes = register(1) & 0x3f;
+        self.hours = register(2) & 0x1f;
+        self.days = register(3) as u16 | ((register(4) as u16 & 0x01) << 8);
+        self.halt = (register(4) & 0x40) == 0x40;
+        self.carry = (register(4) & 0x80) == 0x80;
+        for n in 0..5 {
+            self.latched[n] = register(5 + n);
+        }
+        let mut timestamp = [0; 8];
+        let length = if data.len() >= 48 { 8 } else { 4 };
+        timestamp[..length].copy_from_slice(&data[40..40 + length]);
+        self.host_time = u64::from_le_bytes(timestamp);
+        self.sync_host();
+    }
+
+    fn day_high(&self) -> u8 {
+        let mut result = (self.days >> 8) as u8;
+        if self.halt {
+            result |= 0x40;
+        }
+        if self.carry {
+            result |= 0x80;
+        }
+        return result;
+    }
+
+    fn sync_host(&mut self) {
+        if self.mode != RtcMode::Host {
+            return;
+        }
+        let seconds = host_periods_elapsed(&mut self.host_time, 1);
+        if !self.halt {
+            self.advance(seconds);
+        }
+    }
+
+    fn advance(&mut self, seconds: u64) {
+        let total = self.seconds as u64
+            + self.minutes as u64 * 60
+            + self.hours as u64 * 3600
+            + self.days as u64 * 86400
+            + seconds;
+        self.seconds = (total % 60) as u8;
+        self.minutes = (total / 60 % 60) as u8;
+        self.hours = (total / 3600 % 24) as u8;
+        let days = total / 86400;
+        if days > 0x1ff {
+            self.carry = true;
+        }
+        self.days = (days & 0x1ff) as u16;
+    }
+}
+
+pub fn host_seconds() -> u64 {
+    return SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+}
+
+// Number of whole periods of the given length in seconds the host clock has moved on since
+// `since`, which is advanced by that many periods. A host clock that went back resets it.
+pub fn host_periods_elapsed(since: &mut u64, period: u64) -> u64 {
+    let now = host_seconds();
+    if now < *since {
+        *since = now;
+        return 0;
+    }
+    let periods = (now - *since) / period;
+    *since += periods * period;
+    return periods;
+}
@@ -0,0 +1,59 @@
+// Clocks per bit at the internal 8192 Hz serial clock.
+const BIT_CLOCKS: u32 = 512;
+
+// Serial port without a link partner: a transfer on the internal clock shifts in all ones,
+// a transfer on the external clock never completes.
+pub struct Serial {
+    pub data: u8,
+    control: u8,
+    clocks: u32,
+    // Every byte sent with the internal clock, test ROMs report their results this way.
+    pub output: Vec<u8>,
+
+    pub interrupt: bool,
+}
+
+impl Serial {
+    pub fn new() -> Serial {
+        return Serial {
+            data: 0,
+            control: 0,
+            clocks: 0,
+            output: Vec::new(),
+
+            interrupt: false,
+        };
+    }
+
+    pub fn control(&self) -> u8 {
+        return self.control | 0x7e;
+    }
+
+    pub fn write_control(&mut self, value: u8) {
+        self.control = value & 0x81;
+        if self.control == 0x81 {
+            self.clocks = BIT_CLOCKS * 8;
+            self.output.push(self.data);
+        } else {
+            self.clocks = 0;
+        }
+    }
+
+    pub fn tick(&mut self, clocks: u32) {
+        if self.clocks == 0 {
+            return;
+        }
+        self.clocks = self.clocks.saturating_sub(clocks);
+        if self.clocks == 0 {
+            self.data = 0xff;
+            self.control &= !0x80;
+            self.interrupt = true;
+        }
+    }
+}
+
+impl Default for Serial {
+    fn default() -> Serial {
+        return Serial::new();
+    }
+}
@@ -0,0 +1,98 @@
+pub struct Timer {
+    // Internal 16 bit divider, DIV exposes the upper 8 bits of this.
+    divider: u16,
+    pub tima: u8,
+    pub tma: u8,
+    tac: u8,
+    reload_delay: u8,
+
+    pub interrupt: bool,
+}
+
+impl Timer {
+    pub fn new() -> Timer {
+        return Timer {
+            divider: 0xabcc,
+            tima: 0,
+            tma: 0,
+            tac: 0,
+            reload_delay: 0,
+
+            interrupt: false,
+        };
+    }
+
+    // Advance the timer by a single clock.
+    pub fn step(&mut self) {
+        if self.reload_delay > 0 {
+            self.reload_delay -= 1;
+            if self.reload_delay == 0 {
+                self.tima = self.tma;
+                self.interrupt = true;
+            }
+        }
+        let signal = self.signal();
+        self.divider = self.divider.wrapping_add(1);
+        self.check_falling_edge(signal);
+    }
+
+    pub fn div(&self) -> u8 {
+        return (self.divider >> 8) as u8;
+    }
+
+    pub fn tac(&self) -> u8 {
+        return self.tac | 0xf8;
+    }
+
+    pub fn write_div(&mut self) {
+        let signal = self.signal();
+        self.divider = 0;
+        self.check_falling_edge(signal);
+    }
+
+    pub fn write_tima(&mut self, value: u8) {
+        // Writing TIMA in the cycle after an overflow cancels the reload from TMA.
+        self.reload_delay = 0;
+        self.tima = value;
+    }
+
+    pub fn write_tac(&mut self, value: u8) {
+        let signal = self.signal();
+        self.tac = value & 0x07;
+        self.check_falling_edge(signal);
+    }
+
+    // TIMA is incremented on the falling edge of the selected divider bit ANDed with the enable bit,
+    // so changing DIV or TAC can also cause an increment.
+    fn signal(&self) -> bool {
+        if (self.tac & 0x04) == 0x00 {
+            return false;
+        }
+        let bit = match self.tac & 0x03 {
+            0 => 9,
+            1 => 3,
+            2 => 5,
+            _ => 7,
+        };
+        return (self.divider >> bit) & 0x01 == 0x01;
+    }
+
+    fn check_falling_edge(&mut self, old_signal: bool) {
+        if !old_signal || self.signal() {
+            return;
+        }
+        if self.tima == 0xff {
+            // TIMA reads as zero for one machine cycle before TMA is loaded.
+            self.tima = 0;
+            self.reload_delay = 4;
+        } else {
+            self.tima += 1;
+        }
+    }
+}
+
+impl Default for Timer {
+    fn default() -> Timer {
+        return Timer::new();
+    }
+}
@@ -1,101 +1,306 @@
-extern crate sdl2;
+pub const SCREEN_WIDTH: usize = 160;
+pub const SCREEN_HEIGHT: usize = 144;
+// Gray level of the four DMG shades in the framebuffer, from white to black.
+pub const SHADES: [u8; 4] = [255, 170, 85, 0];
+
+const DOTS_PER_LINE: u32 = 456;
+const LINES_PER_FRAME: u8 = 154;
+pub const CLOCKS_PER_FRAME: u32 = DOTS_PER_LINE * LINES_PER_FRAME as u32;
+// Mode 2 (OAM scan) takes the first 80 dots of a line, mode 3 (drawing) the next 172,
+// the rest of the line is mode 0 (HBlank). Lines 144-153 are mode 1 (VBlank).
+const OAM_SCAN_DOTS: u32 = 80;
+const DRAWING_DOTS: u32 = 172;
 
 pub struct Video {
     pub lcdc: u8,
     pub stat: u8,
+    pub scy: u8,
+    pub scx: u8,
     pub ly: u8,
+    pub lyc: u8,
+    pub wy: u8,
+    pub wx: u8,
+    // Palettes map colors 0-3 to shades, two bits per color starting with color 0 in the low bits.
+    pub bgp: u8,
+    pub obp0: u8,
+    pub obp1: u8,
+    // Line of the window to draw next, only advances on lines where the window was drawn.
+    window_line: u8,
+    // Dot within the current line, or within the frame while the LCD is off.
     counter: u32,
+    enabled: bool,
+    // The STAT interrupt is requested when any of its enabled conditions becomes true.
+    stat_line: bool,
 
     pub vram: [u8; 0x2000],
     pub oam: [u8; 0x100],
     pub vblank_interrupt: bool,
+    pub stat_interrupt: bool,
 
-    canvas: sdl2::render::WindowCanvas,
-    event_pump: sdl2::EventPump,
+    // One shade per pixel, from 0 (white) to 3 (black).
+    pub framebuffer: [u8; SCREEN_WIDTH * SCREEN_HEIGHT],
+    // Set when a complete frame is in the framebuffer.
+    pub frame_ready: bool,
 }
 
 impl Video {
     pub fn new() -> Video {
-        //sdl2::hint::set("SDL_HINT_RENDER_VSYNC", "1");
-
-        let sdl_context = sdl2::init().unwrap();
-        let video_subsystem = sdl_context.video().unwrap();
-        let window = video_subsystem
-            .window("not-so-gb", 160, 144)
-            .position_centered()
-            .build()
-            .unwrap();
-        let canvas = window.into_canvas().build().unwrap();
-        let event_pump = sdl_context.event_pump().unwrap();
-
         return Video {
             lcdc: 0x80,
             stat: 0x00,
+            scy: 0,
+            scx: 0,
             ly: 0,
+            lyc: 0,
+            wy: 0,
+            wx: 0,
+            bgp: 0xfc,
+            obp0: 0xff,
+            obp1: 0xff,
+            window_line: 0,
             counter: 0,
+            enabled: false,
+            stat_line: false,
 
             vram: [0; 0x2000],
             oam: [0; 0x100],
             vblank_interrupt: false,
+            stat_interrupt: false,
 
-            canvas: canvas,
-            event_pump: event_pump,
+            framebuffer: [0; SCREEN_WIDTH * SCREEN_HEIGHT],
+            frame_ready: false,
         };
     }
 
+    // Only the interrupt enable bits of STAT are writable.
+    pub fn write_stat(&mut self, value: u8) {
+        self.stat = (self.stat & 0x07) | (value & 0x78);
+        self.update_stat_line();
+    }
+
+    // Advance the video state by a single dot (one 4.194304 MHz clock).
     pub fn step(&mut self) {
+        if (self.lcdc & 0x80) == 0x00 {
+            self.step_off();
+            return;
+        }
+        if !self.enabled {
+            self.enabled = true;
+            self.counter = 0;
+        }
+
         self.counter += 1;
-        self.stat &= !0x03;
-        if self.ly >= 144 {
-            self.stat |= 0x01;
-        } else if self.counter < 10 {
-            self.stat |= 0x02;
-        } else if self.counter < 50 {
-            self.stat |= 0x03;
-        }
-        if self.counter < 100 {
+        if self.counter == DOTS_PER_LINE {
+            self.counter = 0;
+            self.ly += 1;
+            if self.ly == LINES_PER_FRAME {
+                self.ly = 0;
+            }
+        }
+
+        let mode = if self.ly >= 144 {
+            1
+        } else if self.counter < OAM_SCAN_DOTS {
+            2
+        } else if self.counter < OAM_SCAN_DOTS + DRAWING_DOTS {
+            3
+        } else {
+            0
+        };
+        if mode != (self.stat & 0x03) {
+            if mode == 0 {
+                self.render_line();
+            } else if mode == 1 {
+                self.vblank_interrupt = true;
+                self.frame_ready = true;
+                self.window_line = 0;
+            }
+        }
+        self.stat = (self.stat & !0x03) | mode;
+        if self.ly == self.lyc {
+            self.stat |= 0x04;
+        } else {
+            self.stat &= !0x04;
+        }
+        self.update_stat_line();
+    }
+
+    // With the LCD off LY stays at 0 and the screen is blank, frames still complete at the normal rate.
+    // The LCD is also off while the CPU is in STOP mode.
+    pub fn step_off(&mut self) {
+        if self.enabled {
+            self.enabled = false;
+            self.counter = 0;
+            self.ly = 0;
+            self.window_line = 0;
+            self.stat &= !0x03;
+            self.framebuffer = [0; SCREEN_WIDTH * SCREEN_HEIGHT];
+        }
+        self.counter += 1;
+        if self.counter == DOTS_PER_LINE * LINES_PER_FRAME as u32 {
+            self.counter = 0;
+            self.frame_ready = true;
+        }
+    }
+
+    fn update_stat_line(&mut self) {
+        let mode = self.stat & 0x03;
+        let line = ((self.stat & 0x40) == 0x40 && (self.stat & 0x04) == 0x04)
+            || ((self.stat & 0x20) == 0x20 && mode == 2)
+            || ((self.stat & 0x10) == 0x10 && mode == 1)
+            || ((self.stat & 0x08) == 0x08 && mode == 0);
+        if line && !self.stat_line {
+            self.stat_interrupt = true;
+        }
+        self.stat_line = line;
+    }
+
+    // Draw the current line into the framebuffer, at the end of mode 3.
+    fn render_line(&mut self) {
+        let line = self.ly as usize * SCREEN_WIDTH..(self.ly as usize + 1) * SCREEN_WIDTH;
+        // Background and window color per pixel, objects need these for their priority.
+        let mut colors = [0; SCREEN_WIDTH];
+        if (self.lcdc & 0x01) == 0x01 {
+            self.render_background(&mut colors);
+            if (self.lcdc & 0x20) == 0x20 {
+                self.render_window(&mut colors);
+            }
+            for (shade, color) in self.framebuffer[line].iter_mut().zip(colors.iter()) {
+                *shade = (self.bgp >> (color * 2)) & 0x03;
+            }
+        } else {
+            for shade in self.framebuffer[line].iter_mut() {
+                *shade = 0;
+            }
+        }
+        if (self.lcdc & 0x02) == 0x02 {
+            self.render_objects(&colors);
+        }
+    }
+
+    // The background is a 256x256 pixel map that wraps around, scrolled by SCX/SCY.
+    fn render_background(&self, colors: &mut [u8; SCREEN_WIDTH]) {
+        let y = self.ly as usize;
+        let map = if (self.lcdc & 0x08) == 0x08 {
+            0x1c00
+        } else {
+            0x1800
+        };
+        let bgy = (y + self.scy as usize) & 0xff;
+        for (x, color) in colors.iter_mut().enumerate() {
+            let bgx = (x + self.scx as usize) & 0xff;
+            let tile_number = self.vram[map + bgx / 8 + bgy / 8 * 0x20];
+            *color = self.tile_color(self.tile_data_index(tile_number), bgx % 8, bgy % 8);
+        }
+    }
+
+    // The window covers the background from WX-7, WY down to the bottom right of the screen.
+    fn render_window(&mut self, colors: &mut [u8; SCREEN_WIDTH]) {
+        if self.ly < self.wy || self.wx > 166 {
             return;
         }
-        self.counter = 0;
-        self.ly = self.ly.wrapping_add(1);
-        if self.ly == 145 {
-            self.vblank_interrupt = true;
-
-            self.canvas.set_draw_color(sdl2::pixels::Color::RGB(255, 255, 255));
-            self.canvas.clear();
-
-            for y in 0..144 {
-                let bgy = y;
-                let bg_tile_row = y % 8;
-                for x in 0..160 {
-                    let bgx = x;
-                    let tile_number = self.vram[0x1800 + bgx / 8 + bgy / 8 * 0x20] as usize;
-                    let tile_data_index = tile_number * 16 + bg_tile_row * 2;
-                    let a = self.vram[tile_data_index + 0];
-                    let b = self.vram[tile_data_index + 1];
-                    let bit = 1 << (bgx % 8) as u8;
-                    if (a & bit) == bit && (b & bit) == bit {
-                        self.canvas.set_draw_color(sdl2::pixels::Color::RGB(0, 0, 0));
-                    } else {
-                        self.canvas.set_draw_color(sdl2::pixels::Color::RGB(128, 128, 128));
-                    }
-                    self.canvas
-                        .draw_point(sdl2::rect::Point::new(x as i32, y as i32))
-                        .unwrap();
+        let map = if (self.lcdc & 0x40) == 0x40 {
+            0x1c00
+        } else {
+            0x1800
+        };
+        let window_y = self.window_line as usize;
+        let left = (self.wx as usize).saturating_sub(7);
+        for (x, color) in colors.iter_mut().enumerate().skip(left) {
+            let window_x = x + 7 - self.wx as usize;
+            let tile_number = self.vram[map + window_x / 8 + window_y / 8 * 0x20];
+            *color = self.tile_color(
+                self.tile_data_index(tile_number),
+                window_x % 8,
+                window_y % 8,
+            );
+        }
+        self.window_line += 1;
+    }
+
+    // Up to 10 objects per line are picked in OAM order. Where objects overlap the one with the
+    // smaller X wins, or the one earlier in OAM when X is equal, and color 0 is transparent.
+    fn render_objects(&mut self, colors: &[u8; SCREEN_WIDTH]) {
+        let y = self.ly as i32;
+        let height = if (self.lcdc & 0x04) == 0x04 { 16 } else { 8 };
+        let mut objects = Vec::new();
+        for index in 0..40 {
+            let top = self.oam[index * 4] as i32 - 16;
+            if (top..top + height).contains(&y) {
+                objects.push(index);
+                if objects.len() == 10 {
+                    break;
                 }
             }
-            self.canvas.present();
-
-            for event in self.event_pump.poll_iter() {
-                match event {
-                    sdl2::event::Event::Quit { .. }
-                    | sdl2::event::Event::KeyDown {
-                        keycode: Some(sdl2::keyboard::Keycode::Escape),
-                        ..
-                    } => panic!("QUIT"),
-                    _ => {}
+        }
+        objects.sort_by_key(|index| self.oam[index * 4 + 1]);
+
+        for (x, background) in colors.iter().enumerate() {
+            for index in &objects {
+                let left = self.oam[index * 4 + 1] as i32 - 8;
+                let mut column = x as i32 - left;
+                if !(0..8).contains(&column) {
+                    continue;
+                }
+                let mut row = y - (self.oam[index * 4] as i32 - 16);
+                let mut tile_number = self.oam[index * 4 + 2];
+                let attributes = self.oam[index * 4 + 3];
+                if (attributes & 0x20) == 0x20 {
+                    column = 7 - column;
                 }
+                if (attributes & 0x40) == 0x40 {
+                    row = height - 1 - row;
+                }
+                if height == 16 {
+                    tile_number &= 0xfe;
+                }
+                let color =
+                    self.tile_color(tile_number as usize * 16, column as usize, row as usize);
+                if color == 0 {
+                    continue;
+                }
+                // With the priority bit set background colors 1-3 are drawn over the object.
+                if (attributes & 0x80) == 0x80 && *background != 0 {
+                    break;
+                }
+                let palette = if (attributes & 0x10) == 0x10 {
+                    self.obp1
+                } else {
+                    self.obp0
+                };
+                self.framebuffer[y as usize * SCREEN_WIDTH + x] = (palette >> (color * 2)) & 0x03;
+                break;
             }
         }
     }
+
+    // With LCDC bit 4 set tiles 0-255 are at 0x8000, otherwise tiles -128-127 are around 0x9000.
+    fn tile_data_index(&self, tile_number: u8) -> usize {
+        if (self.lcdc & 0x10) == 0x10 {
+            return tile_number as usize * 16;
+        }
+        return (0x1000 + (tile_number as i8 as i32) * 16) as usize;
+    }
+
+    // Color 0-3 of a pixel in a tile. Each row is two bytes, the first holds the low bit and the
+    // second the high bit of the color, with the leftmost pixel in bit 7.
+    fn tile_color(&self, tile_data_index: usize, x: usize, y: usize) -> u8 {
+        let a = self.vram[tile_data_index + y * 2];
+        let b = self.vram[tile_data_index + y * 2 + 1];
+        let bit = 0x80 >> x;
+        let mut color = 0;
+        if (a & bit) == bit {
+            color |= 0x01;
+        }
+        if (b & bit) == bit {
+            color |= 0x02;
+        }
+        return color;
+    }
+}
+
+impl Default for Video {
+    fn default() -> Video {
+        return Video::new();
+    }
 }
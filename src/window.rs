@@ -0,0 +1,148 @@
+extern crate sdl2;
+
+use not_so_gb::video::{CLOCKS_PER_FRAME, SCREEN_HEIGHT, SCREEN_WIDTH, SHADES};
+use std::time::{Duration, Instant};
+
+// Frames are shown at the Game Boy refresh rate of about 59.7 Hz.
+const FRAME_TIME: Duration =
+    Duration::from_nanos(1_000_000_000 * CLOCKS_PER_FRAME as u64 / 4194304);
+
+// SDL window showing the emulated screen and turning keyboard and mouse input into buttons and tilt.
+pub struct Window {
+    // Pressed buttons: right, left, up, down, A, B, select, start from bit 0 to 7.
+    pub buttons: u8,
+    pub quit: bool,
+    // Accelerometer input for tilt carts, from the mouse position or the I/J/K/L keys.
+    pub tilt_x: f32,
+    pub tilt_y: f32,
+    scale: u32,
+    next_frame: Instant,
+
+    canvas: sdl2::render::WindowCanvas,
+    event_pump: sdl2::EventPump,
+}
+
+impl Window {
+    pub fn new(scale: u32) -> Result<Window, String> {
+        let sdl_context = sdl2::init()?;
+        let video_subsystem = sdl_context.video()?;
+        let window = video_subsystem
+            .window(
+                "not-so-gb",
+                SCREEN_WIDTH as u32 * scale,
+                SCREEN_HEIGHT as u32 * scale,
+            )
+            .position_centered()
+            .build()
+            .map_err(|e| e.to_string())?;
+        let mut canvas = window.into_canvas().build().map_err(|e| e.to_string())?;
+        canvas.set_scale(scale as f32, scale as f32)?;
+        let event_pump = sdl_context.event_pump()?;
+
+        return Ok(Window {
+            buttons: 0,
+            quit: false,
+            tilt_x: 0.0,
+            tilt_y: 0.0,
+            scale: scale,
+            next_frame: Instant::now(),
+
+            canvas: canvas,
+            event_pump: event_pump,
+        });
+    }
+
+    // Draw a frame, with a red indicator in the corner while the cartridge rumble motor runs,
+    // then wait until it is time for the next frame.
+    pub fn present(&mut self, framebuffer: &[u8], rumble: bool) {
+        for y in 0..SCREEN_HEIGHT {
+            for x in 0..SCREEN_WIDTH {
+                let shade = SHADES[framebuffer[y * SCREEN_WIDTH + x] as usize & 0x03];
+                self.canvas
+                    .set_draw_color(sdl2::pixels::Color::RGB(shade, shade, shade));
+                self.canvas
+                    .draw_point(sdl2::rect::Point::new(x as i32, y as i32))
+                    .unwrap();
+            }
+        }
+        if rumble {
+            self.canvas
+                .set_draw_color(sdl2::pixels::Color::RGB(255, 0, 0));
+            self.canvas
+                .fill_rect(sdl2::rect::Rect::new(SCREEN_WIDTH as i32 - 4, 0, 4, 4))
+                .unwrap();
+        }
+        self.canvas.present();
+
+        // Wait for the next frame, or start over when we are more than a frame behind.
+        let now = Instant::now();
+        if self.next_frame > now {
+            std::thread::sleep(self.next_frame - now);
+            self.next_frame += FRAME_TIME;
+        } else {
+            self.next_frame = now + FRAME_TIME;
+        }
+    }
+
+    pub fn poll_events(&mut self) {
+        for event in self.event_pump.poll_iter() {
+            match event {
+                sdl2::event::Event::Quit { .. }
+                | sdl2::event::Event::KeyDown {
+                    keycode: Some(sdl2::keyboard::Keycode::Escape),
+                    ..
+                } => self.quit = true,
+                sdl2::event::Event::KeyDown {
+                    keycode: Some(keycode),
+                    ..
+                } => {
+                    self.buttons |= Window::button_for_key(keycode);
+                    match keycode {
+                        sdl2::keyboard::Keycode::J => self.tilt_x = -1.0,
+                        sdl2::keyboard::Keycode::L => self.tilt_x = 1.0,
+                        sdl2::keyboard::Keycode::I => self.tilt_y = -1.0,
+                        sdl2::keyboard::Keycode::K => self.tilt_y = 1.0,
+                        _ => {}
+                    }
+                }
+                sdl2::event::Event::KeyUp {
+                    keycode: Some(keycode),
+                    ..
+                } => {
+                    self.buttons &= !Window::button_for_key(keycode);
+                    match keycode {
+                        sdl2::keyboard::Keycode::J | sdl2::keyboard::Keycode::L => {
+                            self.tilt_x = 0.0
+                        }
+                        sdl2::keyboard::Keycode::I | sdl2::keyboard::Keycode::K => {
+                            self.tilt_y = 0.0
+                        }
+                        _ => {}
+                    }
+                }
+                sdl2::event::Event::MouseMotion { x, y, .. } => {
+                    // The window center is level, the window edges are a full g of tilt.
+                    let width = (SCREEN_WIDTH as u32 * self.scale) as f32;
+                    let height = (SCREEN_HEIGHT as u32 * self.scale) as f32;
+                    self.tilt_x = (x as f32 / width * 2.0 - 1.0).clamp(-1.0, 1.0);
+                    self.tilt_y = (y as f32 / height * 2.0 - 1.0).clamp(-1.0, 1.0);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn button_for_key(keycode: sdl2::keyboard::Keycode) -> u8 {
+        return match keycode {
+            sdl2::keyboard::Keycode::Right => 0x01,
+            sdl2::keyboard::Keycode::Left => 0x02,
+            sdl2::keyboard::Keycode::Up => 0x04,
+            sdl2::keyboard::Keycode::Down => 0x08,
+            sdl2::keyboard::Keycode::X => 0x10,
+            sdl2::keyboard::Keycode::Z => 0x20,
+            sdl2::keyboard::Keycode::RShift => 0x40,
+            sdl2::keyboard::Keycode::Return => 0x80,
+            _ => 0x00,
+        };
+    }
+}
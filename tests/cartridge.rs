@@ -0,0 +1,152 @@
+use not_so_gb::cartridge::Cartridge;
+use not_so_gb::mbc::RtcMode;
+
+// A ROM of the given number of 16 KiB banks, with every bank filled with its own bank number.
+fn rom_with_banks(cartridge_type: u8, size_code: u8, banks: usize) -> Vec<u8> {
+    let mut rom = Vec::new();
+    for bank in 0..banks {
+        rom.extend(vec![bank as u8; 0x4000]);
+    }
+    rom[0x147] = cartridge_type;
+    rom[0x148] = size_code;
+    return rom;
+}
+
+// Map flash bank 2 at 0x4000 and bank 1 at 0x6000, so the command addresses 0x5555 and
+// 0x2aaa of the flash chip show up at 0x5555 and 0x6aaa.
+fn mbc6_with_flash() -> Cartridge {
+    let mut cartridge = Cartridge::new(rom_with_banks(0x20, 0x01, 4), RtcMode::Emulated).unwrap();
+    cartridge.write(0x0c00, 0x01);
+    cartridge.write(0x1000, 0x01);
+    cartridge.write(0x2000, 0x02);
+    cartridge.write(0x2800, 0x08);
+    cartridge.write(0x3000, 0x01);
+    cartridge.write(0x3800, 0x08);
+    return cartridge;
+}
+
+#[test]
+fn short_rom_is_rejected() {
+    assert!(Cartridge::new(vec![0; 0x14f], RtcMode::Emulated).is_err());
+}
+
+fn flash_command(cartridge: &mut Cartridge, command: u8) {
+    cartridge.write(0x5555, 0xaa);
+    cartridge.write(0x6aaa, 0x55);
+    cartridge.write(0x5555, command);
+}
+
+#[test]
+fn mbc6_flash_program_and_erase() {
+    let mut cartridge = mbc6_with_flash();
+    // Writes outside of a command sequence leave the flash alone.
+    cartridge.write(0x4000, 0x00);
+    assert_eq!(cartridge.read(0x4000), 0xff);
+
+    flash_command(&mut cartridge, 0xa0);
+    cartridge.write(0x4000, 0x12);
+    assert_eq!(cartridge.read(0x4000), 0x12);
+    // The unlock writes themselves must not end up in the flash.
+    assert_eq!(cartridge.read(0x5555), 0xff);
+    assert_eq!(cartridge.read(0x6aaa), 0xff);
+
+    flash_command(&mut cartridge, 0x80);
+    flash_command(&mut cartridge, 0x10);
+    assert_eq!(cartridge.read(0x4000), 0xff);
+}
+
+#[test]
+fn mbc1_bank_zero_selects_next_bank() {
+    let mut cartridge = Cartridge::new(rom_with_banks(0x01, 0x05, 64), RtcMode::Emulated).unwrap();
+    assert_eq!(cartridge.read(0x4000), 0x01);
+    cartridge.write(0x2000, 0x00);
+    assert_eq!(cartridge.read(0x4000), 0x01);
+    cartridge.write(0x2000, 0x05);
+    assert_eq!(cartridge.read(0x4000), 0x05);
+    // The zero check looks at all 5 bits, so bank 0x20 can not be selected and maps 0x21.
+    cartridge.write(0x2000, 0x20);
+    cartridge.write(0x4000, 0x01);
+    assert_eq!(cartridge.read(0x4000), 0x21);
+    assert_eq!(cartridge.read(0x0000), 0x00);
+    // In advanced banking mode the upper bits also switch the 0x0000-0x3fff area.
+    cartridge.write(0x6000, 0x01);
+    assert_eq!(cartridge.read(0x0000), 0x20);
+}
+
+#[test]
+fn mbc1_multicart_uses_4_bit_low_bank() {
+    let logo = [
+        0xce, 0xed, 0x66, 0x66, 0xcc, 0x0d, 0x00, 0x0b, 0x03, 0x73, 0x00, 0x83, 0x00, 0x0c, 0x00,
+        0x0d, 0x00, 0x08, 0x11, 0x1f, 0x88, 0x89, 0x00, 0x0e, 0xdc, 0xcc, 0x6e, 0xe6, 0xdd, 0xdd,
+        0xd9, 0x99, 0xbb, 0xbb, 0x67, 0x63, 0x6e, 0x0e, 0xec, 0xcc, 0xdd, 0xdc, 0x99, 0x9f, 0xbb,
+        0xb9, 0x33, 0x3e,
+    ];
+    let mut rom = rom_with_banks(0x01, 0x05, 64);
+    rom[0x40104..0x40134].copy_from_slice(&logo);
+    let mut cartridge = Cartridge::new(rom, RtcMode::Emulated).unwrap();
+    cartridge.write(0x2000, 0x12);
+    cartridge.write(0x4000, 0x01);
+    assert_eq!(cartridge.read(0x4000), 0x12);
+    // 0x10 passes the zero check but only its low 4 bits reach the ROM, selecting bank 0 of the game.
+    cartridge.write(0x2000, 0x10);
+    assert_eq!(cartridge.read(0x4000), 0x10);
+}
+
+// An MBC3 cart with its clock running on emulated time, with the clock registers mapped.
+fn mbc3_with_rtc() -> Cartridge {
+    let mut cartridge = Cartridge::new(rom_with_banks(0x10, 0x01, 4), RtcMode::Emulated).unwrap();
+    cartridge.write(0x0000, 0x0a);
+    return cartridge;
+}
+
+fn write_rtc(cartridge: &mut Cartridge, register: u8, value: u8) {
+    cartridge.write(0x4000, register);
+    cartridge.write(0xa000, value);
+}
+
+fn latch_and_read_rtc(cartridge: &mut Cartridge, register: u8) -> u8 {
+    cartridge.write(0x6000, 0x00);
+    cartridge.write(0x6000, 0x01);
+    cartridge.write(0x4000, register);
+    return cartridge.read(0xa000);
+}
+
+const CLOCKS_PER_SECOND: u32 = 4194304;
+
+#[test]
+fn mbc3_rtc_reads_latched_time() {
+    let mut cartridge = mbc3_with_rtc();
+    cartridge.tick(CLOCKS_PER_SECOND * 3);
+    cartridge.write(0x4000, 0x08);
+    assert_eq!(cartridge.read(0xa000), 0);
+    assert_eq!(latch_and_read_rtc(&mut cartridge, 0x08), 3);
+    // The latched value holds until the next latch.
+    cartridge.tick(CLOCKS_PER_SECOND);
+    assert_eq!(cartridge.read(0xa000), 3);
+    assert_eq!(latch_and_read_rtc(&mut cartridge, 0x08), 4);
+}
+
+#[test]
+fn mbc3_rtc_halt_stops_clock() {
+    let mut cartridge = mbc3_with_rtc();
+    write_rtc(&mut cartridge, 0x0c, 0x40);
+    cartridge.tick(CLOCKS_PER_SECOND * 5);
+    assert_eq!(latch_and_read_rtc(&mut cartridge, 0x08), 0);
+    write_rtc(&mut cartridge, 0x0c, 0x00);
+    cartridge.tick(CLOCKS_PER_SECOND * 5);
+    assert_eq!(latch_and_read_rtc(&mut cartridge, 0x08), 5);
+}
+
+#[test]
+fn mbc3_rtc_day_counter_overflow_sets_carry() {
+    let mut cartridge = mbc3_with_rtc();
+    write_rtc(&mut cartridge, 0x0b, 0xff);
+    write_rtc(&mut cartridge, 0x0c, 0x01);
+    write_rtc(&mut cartridge, 0x0a, 23);
+    write_rtc(&mut cartridge, 0x09, 59);
+    write_rtc(&mut cartridge, 0x08, 59);
+    cartridge.tick(CLOCKS_PER_SECOND);
+    assert_eq!(latch_and_read_rtc(&mut cartridge, 0x0b), 0x00);
+    assert_eq!(latch_and_read_rtc(&mut cartridge, 0x0c), 0x80);
+    assert_eq!(latch_and_read_rtc(&mut cartridge, 0x0a), 0);
+}
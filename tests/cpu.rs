@@ -0,0 +1,103 @@
+use not_so_gb::bus::{Bus, FlatBus};
+use not_so_gb::cpu::{Model, CPU};
+
+// Plain RAM that requests a VBlank interrupt once the given number of machine cycles have passed.
+struct InterruptBus {
+    memory: Vec<u8>,
+    cycles: u32,
+    interrupt_at: Option<u32>,
+}
+
+impl Bus for InterruptBus {
+    fn read(&self, addr: u16) -> u8 {
+        return self.memory[addr as usize];
+    }
+
+    fn write(&mut self, addr: u16, value: u8) {
+        self.memory[addr as usize] = value;
+    }
+
+    fn tick(&mut self, cycles: u32) {
+        self.cycles += cycles;
+        if let Some(interrupt_at) = self.interrupt_at {
+            if self.cycles >= interrupt_at {
+                self.interrupt_at = None;
+                self.memory[0xff0f] |= 0x01;
+            }
+        }
+    }
+}
+
+// Start a CPU in the post-boot state with the program at 0x0100.
+fn cpu_with_program(program: &[u8]) -> CPU<FlatBus> {
+    let mut bus = FlatBus::new();
+    bus.memory[0x0100..0x0100 + program.len()].copy_from_slice(program);
+    return CPU::new(bus, Model::DMG);
+}
+
+#[test]
+fn add_and_store() {
+    // LD A,0x12; ADD A,0x34; LD (0xc000),A
+    let mut cpu = cpu_with_program(&[0x3e, 0x12, 0xc6, 0x34, 0xea, 0x00, 0xc0]);
+    assert_eq!(cpu.step(), 2);
+    assert_eq!(cpu.step(), 2);
+    assert_eq!(cpu.step(), 4);
+    assert_eq!(cpu.bus.memory[0xc000], 0x46);
+}
+
+#[test]
+fn push_wraps_stack_pointer() {
+    // LD SP,0x0000; PUSH BC
+    let mut cpu = cpu_with_program(&[0x31, 0x00, 0x00, 0xc5]);
+    cpu.step();
+    cpu.step();
+    assert_eq!(cpu.bus.memory[0xfffe], 0x13);
+    assert_eq!(cpu.bus.memory[0xffff], 0x00);
+}
+
+#[test]
+fn illegal_opcode_locks_up() {
+    // An illegal opcode followed by LD A,0x55; LD (0xc000),A, which must never run.
+    let mut cpu = cpu_with_program(&[0xd3, 0x3e, 0x55, 0xea, 0x00, 0xc0]);
+    for _ in 0..10 {
+        cpu.step();
+    }
+    assert_eq!(cpu.bus.memory[0xc000], 0x00);
+}
+
+// A VBlank handler at 0x0040 that runs LD A,0x42; LD (0xc000),A; RETI.
+const VBLANK_HANDLER: [u8; 6] = [0x3e, 0x42, 0xea, 0x00, 0xc0, 0xd9];
+
+#[test]
+fn interrupt_during_halt_runs_handler() {
+    // EI; NOP; HALT, with VBlank requested during the machine cycle of the HALT itself.
+    let mut bus = InterruptBus {
+        memory: vec![0; 0x10000],
+        cycles: 0,
+        interrupt_at: Some(3),
+    };
+    bus.memory[0x0100..0x0103].copy_from_slice(&[0xfb, 0x00, 0x76]);
+    bus.memory[0x0040..0x0046].copy_from_slice(&VBLANK_HANDLER);
+    bus.memory[0xffff] = 0x01;
+    let mut cpu = CPU::new(bus, Model::DMG);
+    for _ in 0..10 {
+        cpu.step();
+    }
+    assert_eq!(cpu.bus.memory[0xc000], 0x42);
+}
+
+#[test]
+fn ei_halt_with_pending_interrupt_returns_to_halt() {
+    // EI; HALT with VBlank already requested, the handler must run from its first byte.
+    let mut cpu = cpu_with_program(&[0xfb, 0x76]);
+    cpu.bus.memory[0x0040..0x0046].copy_from_slice(&VBLANK_HANDLER);
+    cpu.bus.memory[0xff0f] = 0x01;
+    cpu.bus.memory[0xffff] = 0x01;
+    for _ in 0..4 {
+        cpu.step();
+    }
+    assert_eq!(cpu.bus.memory[0xc000], 0x42);
+    // The return address on the stack is the HALT itself.
+    assert_eq!(cpu.bus.memory[0xfffc], 0x01);
+    assert_eq!(cpu.bus.memory[0xfffd], 0x01);
+}
@@ -0,0 +1,74 @@
+use not_so_gb::timer::Timer;
+
+// A timer counting on bit 3 of the divider, so TIMA increments every 16 clocks starting from
+// a reset divider.
+fn timer_at(tima: u8, tma: u8) -> Timer {
+    let mut timer = Timer::new();
+    timer.write_tac(0x05);
+    timer.write_div();
+    timer.write_tima(tima);
+    timer.tma = tma;
+    return timer;
+}
+
+fn step(timer: &mut Timer, clocks: u32) {
+    for _ in 0..clocks {
+        timer.step();
+    }
+}
+
+#[test]
+fn counts_at_selected_rate() {
+    let mut timer = timer_at(0x00, 0x00);
+    step(&mut timer, 15);
+    assert_eq!(timer.tima, 0x00);
+    step(&mut timer, 1);
+    assert_eq!(timer.tima, 0x01);
+    step(&mut timer, 16 * 4);
+    assert_eq!(timer.tima, 0x05);
+}
+
+#[test]
+fn overflow_reloads_after_one_machine_cycle() {
+    let mut timer = timer_at(0xff, 0x23);
+    step(&mut timer, 16);
+    assert_eq!(timer.tima, 0x00);
+    assert!(!timer.interrupt);
+    step(&mut timer, 3);
+    assert_eq!(timer.tima, 0x00);
+    assert!(!timer.interrupt);
+    step(&mut timer, 1);
+    assert_eq!(timer.tima, 0x23);
+    assert!(timer.interrupt);
+}
+
+#[test]
+fn tima_write_cancels_reload() {
+    let mut timer = timer_at(0xff, 0x23);
+    step(&mut timer, 16);
+    timer.write_tima(0x50);
+    step(&mut timer, 4);
+    assert_eq!(timer.tima, 0x50);
+    assert!(!timer.interrupt);
+}
+
+#[test]
+fn div_write_on_high_bit_increments() {
+    let mut timer = timer_at(0x00, 0x00);
+    step(&mut timer, 8);
+    timer.write_div();
+    assert_eq!(timer.tima, 0x01);
+    // With the selected bit low a reset causes no increment.
+    timer.write_div();
+    assert_eq!(timer.tima, 0x01);
+}
+
+#[test]
+fn disabling_timer_on_high_bit_increments() {
+    let mut timer = timer_at(0x00, 0x00);
+    step(&mut timer, 8);
+    timer.write_tac(0x01);
+    assert_eq!(timer.tima, 0x01);
+    step(&mut timer, 32);
+    assert_eq!(timer.tima, 0x01);
+}